@@ -0,0 +1,151 @@
+use glam::Vec3;
+
+/// Advances a ball by one step of `dt`, given the acceleration at any point.
+pub trait Integrator {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3);
+}
+
+/// Semi-implicit Euler: kick with the current acceleration, then drift.
+pub struct Euler;
+
+impl Integrator for Euler {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3) {
+        *vel += acc(*pos) * dt;
+        *pos += *vel * dt;
+    }
+}
+
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3) {
+        let a0 = acc(*pos);
+        *pos += *vel * dt + 0.5 * a0 * dt * dt;
+        let a1 = acc(*pos);
+        *vel += 0.5 * (a0 + a1) * dt;
+    }
+}
+
+/// Drift-kick-drift leapfrog.
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3) {
+        *pos += *vel * (0.5 * dt);
+        *vel += acc(*pos) * dt;
+        *pos += *vel * (0.5 * dt);
+    }
+}
+
+/// Classic fourth order Runge-Kutta.
+pub struct Rk4;
+
+impl Integrator for Rk4 {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3) {
+        let (p0, v0) = (*pos, *vel);
+
+        let k1_p = v0;
+        let k1_v = acc(p0);
+        let k2_p = v0 + k1_v * (0.5 * dt);
+        let k2_v = acc(p0 + k1_p * (0.5 * dt));
+        let k3_p = v0 + k2_v * (0.5 * dt);
+        let k3_v = acc(p0 + k2_p * (0.5 * dt));
+        let k4_p = v0 + k3_v * dt;
+        let k4_v = acc(p0 + k3_p * dt);
+
+        *pos = p0 + (k1_p + 2.0 * k2_p + 2.0 * k3_p + k4_p) * (dt / 6.0);
+        *vel = v0 + (k1_v + 2.0 * k2_v + 2.0 * k3_v + k4_v) * (dt / 6.0);
+    }
+}
+
+/// Which integrator a `World` steps its balls with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntegratorKind {
+    #[default]
+    Euler,
+    VelocityVerlet,
+    Leapfrog,
+    Rk4,
+}
+
+impl IntegratorKind {
+    pub fn next(self) -> Self {
+        match self {
+            IntegratorKind::Euler => IntegratorKind::VelocityVerlet,
+            IntegratorKind::VelocityVerlet => IntegratorKind::Leapfrog,
+            IntegratorKind::Leapfrog => IntegratorKind::Rk4,
+            IntegratorKind::Rk4 => IntegratorKind::Euler,
+        }
+    }
+}
+
+impl Integrator for IntegratorKind {
+    fn step(&self, pos: &mut Vec3, vel: &mut Vec3, dt: f32, acc: &dyn Fn(Vec3) -> Vec3) {
+        match self {
+            IntegratorKind::Euler => Euler.step(pos, vel, dt, acc),
+            IntegratorKind::VelocityVerlet => VelocityVerlet.step(pos, vel, dt, acc),
+            IntegratorKind::Leapfrog => Leapfrog.step(pos, vel, dt, acc),
+            IntegratorKind::Rk4 => Rk4.step(pos, vel, dt, acc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Planet, World, GRAVITY};
+
+    const RADIUS: f32 = 2.0;
+    const ORBITS: f32 = 10.0;
+    const DT: f32 = 1.0 / 240.0;
+    const MAX_DRIFT: f32 = 1e-4;
+
+    fn energy(planet: &Planet, pos: Vec3, vel: Vec3) -> f32 {
+        0.5 * vel.length_squared() - GRAVITY * planet.mass / (pos - planet.pos).length()
+    }
+
+    /// Relative change in specific orbital energy after `ORBITS` circular
+    /// orbits around a planet at the origin, at the game's step size.
+    fn drift(integrator: &dyn Integrator) -> f32 {
+        let planet = Planet::new(Vec3::ZERO, 800.0, 1.0);
+        let world = World {
+            planets: vec![planet.clone()],
+            ..Default::default()
+        };
+
+        let speed = (GRAVITY * planet.mass / RADIUS).sqrt();
+        let period = std::f32::consts::TAU * RADIUS / speed;
+
+        let mut pos = Vec3::new(RADIUS, 0.0, 0.0);
+        let mut vel = Vec3::new(0.0, 0.0, speed);
+        let start = energy(&planet, pos, vel);
+
+        let acc = |p| world.acceleration_at(p);
+        for _ in 0..(ORBITS * period / DT) as usize {
+            integrator.step(&mut pos, &mut vel, DT, &acc);
+        }
+
+        ((energy(&planet, pos, vel) - start) / start).abs()
+    }
+
+    #[test]
+    fn euler_drift() {
+        assert!(drift(&Euler) < MAX_DRIFT);
+    }
+
+    #[test]
+    fn velocity_verlet_drift() {
+        assert!(drift(&VelocityVerlet) < MAX_DRIFT);
+    }
+
+    #[test]
+    fn leapfrog_drift() {
+        assert!(drift(&Leapfrog) < MAX_DRIFT);
+    }
+
+    #[test]
+    fn rk4_drift() {
+        assert!(drift(&Rk4) < MAX_DRIFT);
+    }
+}
@@ -0,0 +1,701 @@
+//! The gravity and collision maths behind the game, with no Bevy in sight.
+
+use glam::Vec3;
+
+mod integrator;
+pub use integrator::*;
+mod motion;
+pub use motion::*;
+mod octree;
+pub use octree::Octree;
+pub mod solve;
+
+pub const GRAVITY: f32 = 6.67e-2;
+
+/// Fraction of its normal speed a ball keeps when it bounces off a planet,
+/// unless the planet says otherwise.
+pub const DEFAULT_RESTITUTION: f32 = 0.75;
+
+/// Coulomb friction coefficient between the ball and a planet's surface.
+pub const DEFAULT_FRICTION: f32 = 0.4;
+
+/// Below this speed into the surface the ball stays in contact rather than
+/// bouncing.
+pub const BOUNCE_SPEED: f32 = 0.5;
+
+/// Below this speed relative to the surface a ball in contact is at rest.
+pub const REST_SPEED: f32 = 0.05;
+
+/// How close the ball has to get to the goal to finish a level.
+pub const GOAL_REACH: f32 = 0.3;
+
+/// How far from the planets the ball can go before it's counted as lost: a
+/// multiple of the radius from `World::bounds`, plus a margin so levels with
+/// a single small planet still leave room to shoot.
+pub const OUT_OF_BOUNDS_SCALE: f32 = 3.0;
+pub const OUT_OF_BOUNDS_MARGIN: f32 = 20.0;
+
+pub fn acc_of(m2: f32, p1: Vec3, p2: Vec3) -> Vec3 {
+    (p2 - p1).normalize() * GRAVITY * m2 / (p1 - p2).length_squared()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Planet {
+    pub pos: Vec3,
+    pub vel: Vec3,
+    pub mass: f32,
+    pub radius: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub motion: Motion,
+}
+
+impl Planet {
+    pub fn new(pos: Vec3, mass: f32, radius: f32) -> Self {
+        Self {
+            pos,
+            vel: Vec3::ZERO,
+            mass,
+            radius,
+            restitution: DEFAULT_RESTITUTION,
+            friction: DEFAULT_FRICTION,
+            motion: Motion::Static,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ball {
+    pub pos: Vec3,
+    pub vel: Vec3,
+    pub radius: f32,
+}
+
+impl Ball {
+    pub fn new(pos: Vec3, vel: Vec3, radius: f32) -> Self {
+        Self {
+            pos,
+            vel,
+            radius,
+        }
+    }
+}
+
+/// What happened to a ball in `World::collide`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Collision {
+    None,
+    Bounced(usize),
+    /// Touching the surface without bouncing, rolling or sliding along it.
+    Contact(usize),
+    /// In contact and slow enough to have come to rest on this planet.
+    Landed(usize),
+}
+
+/// Where a predicted ball goes: its position after every step, where it
+/// bounced, and where it came to rest if it did.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    pub points: Vec<Vec3>,
+    pub bounces: Vec<Vec3>,
+    pub rest: Option<Vec3>,
+}
+
+/// Settings for letting `Motion::Free` planets pull on each other. Every
+/// planet pulls, but only free ones are pushed around.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct NBody {
+    /// Barnes-Hut opening angle, bigger is faster and less accurate.
+    pub theta: f32,
+    /// Below this many planets the forces are summed directly.
+    pub direct_below: usize,
+}
+
+impl Default for NBody {
+    fn default() -> Self {
+        Self {
+            theta: 0.5,
+            direct_below: 64,
+        }
+    }
+}
+
+/// Where a ball in flight is heading, judged by its orbital energy against
+/// the planets as a whole.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fate {
+    /// Will come back, after about `period` seconds for a full orbit.
+    Bound { period: f32 },
+    /// Moving away fast enough that it never comes back.
+    Escaping,
+}
+
+/// A ball coming to rest on `planet` during a step.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Landing {
+    pub ball: usize,
+    pub planet: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct World {
+    pub planets: Vec<Planet>,
+    pub balls: Vec<Ball>,
+    pub integrator: IntegratorKind,
+    pub time: f32,
+    /// Whether free planets attract each other. Without it they drift at
+    /// constant velocity.
+    pub n_body: Option<NBody>,
+}
+
+impl World {
+    /// Moves every planet with a `Motion` to where it is at `time`. Static
+    /// planets stay where they are.
+    pub fn update_planets(&mut self) {
+        for i in 0..self.planets.len() {
+            let (pos, vel) = match &self.planets[i].motion {
+                Motion::Static | Motion::Free => continue,
+                Motion::Orbit(orbit) => {
+                    let parent = &self.planets[orbit.parent];
+                    let (pos, vel) = orbit.state_at(parent.mass, self.time);
+                    (parent.pos + pos, parent.vel + vel)
+                }
+                Motion::Path(path) => path.state_at(self.time),
+            };
+
+            self.planets[i].pos = pos;
+            self.planets[i].vel = vel;
+        }
+    }
+
+    /// Moves time, and the planets with it, on by `dt`. Free planets take a
+    /// velocity Verlet step whatever the ball's integrator is, since it keeps
+    /// momentum and energy in check for the system as a whole.
+    pub fn advance(&mut self, dt: f32) {
+        if !self.planets.iter().any(|planet| planet.motion == Motion::Free) {
+            self.time += dt;
+            self.update_planets();
+            return;
+        }
+
+        let before = self.planet_accelerations();
+        for (planet, acc) in self.planets.iter_mut().zip(&before) {
+            if planet.motion == Motion::Free {
+                planet.pos += planet.vel * dt + 0.5 * *acc * dt * dt;
+            }
+        }
+
+        // Orbits around free planets follow them to their new positions.
+        self.time += dt;
+        self.update_planets();
+
+        let after = self.planet_accelerations();
+        for (planet, (before, after)) in self.planets.iter_mut().zip(before.iter().zip(&after)) {
+            if planet.motion == Motion::Free {
+                planet.vel += 0.5 * (*before + *after) * dt;
+            }
+        }
+    }
+
+    /// Pull of every other planet on each free planet, or zero for the rest.
+    fn planet_accelerations(&self) -> Vec<Vec3> {
+        let n_body = match self.n_body {
+            Some(n_body) => n_body,
+            None => return vec![Vec3::ZERO; self.planets.len()],
+        };
+
+        let bodies = self.planets
+            .iter()
+            .map(|planet| (planet.pos, planet.mass))
+            .collect::<Vec<_>>();
+        let tree = (bodies.len() >= n_body.direct_below).then(|| Octree::new(&bodies));
+
+        self.planets
+            .iter()
+            .enumerate()
+            .map(|(i, planet)| {
+                if planet.motion != Motion::Free {
+                    return Vec3::ZERO;
+                }
+                match &tree {
+                    Some(tree) => tree.acceleration_at(planet.pos, Some(i), n_body.theta),
+                    None => bodies
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .fold(Vec3::ZERO, |acc, (_, (pos, mass))| acc + acc_of(*mass, planet.pos, *pos)),
+                }
+            })
+            .collect()
+    }
+
+    /// Centre and radius of a sphere around every planet.
+    pub fn bounds(&self) -> (Vec3, f32) {
+        if self.planets.is_empty() {
+            return (Vec3::ZERO, 0.0);
+        }
+
+        let centre = self.planets.iter().fold(Vec3::ZERO, |sum, planet| sum + planet.pos)
+            / self.planets.len() as f32;
+        let radius = self.planets
+            .iter()
+            .map(|planet| (planet.pos - centre).length() + planet.radius)
+            .fold(0.0, f32::max);
+        (centre, radius)
+    }
+
+    /// Position, velocity and mass of the planets' barycentre, counting only
+    /// planets that attract.
+    pub fn barycentre(&self) -> (Vec3, Vec3, f32) {
+        let (pos, vel, mass) = self.planets
+            .iter()
+            .filter(|planet| planet.mass > 0.0)
+            .fold((Vec3::ZERO, Vec3::ZERO, 0.0), |(pos, vel, mass), planet| {
+                (pos + planet.pos * planet.mass, vel + planet.vel * planet.mass, mass + planet.mass)
+            });
+        if mass > 0.0 {
+            (pos / mass, vel / mass, mass)
+        } else {
+            (Vec3::ZERO, Vec3::ZERO, 0.0)
+        }
+    }
+
+    /// Gravitational potential energy per unit mass at `pos`.
+    pub fn potential_at(&self, pos: Vec3) -> f32 {
+        self.planets
+            .iter()
+            .map(|planet| -GRAVITY * planet.mass / (pos - planet.pos).length())
+            .sum()
+    }
+
+    /// Kinetic plus potential energy per unit mass, with the velocity taken
+    /// relative to the barycentre.
+    pub fn specific_energy(&self, ball: &Ball) -> f32 {
+        let (_, vel, _) = self.barycentre();
+        0.5 * (ball.vel - vel).length_squared() + self.potential_at(ball.pos)
+    }
+
+    /// Treats the planets as one mass at their barycentre: positive energy
+    /// while moving away is an escape, otherwise the period of the ellipse
+    /// the ball is on says how long it'll be gone.
+    pub fn fate(&self, ball: &Ball) -> Fate {
+        let (pos, vel, mass) = self.barycentre();
+        let energy = self.specific_energy(ball);
+        let receding = (ball.pos - pos).dot(ball.vel - vel) > 0.0;
+
+        if mass <= 0.0 || (energy >= 0.0 && receding) {
+            return Fate::Escaping;
+        }
+        if energy >= 0.0 {
+            // Still falling in for now, it'll be back at least once.
+            return Fate::Bound { period: 0.0 };
+        }
+
+        let semi_major = -GRAVITY * mass / (2.0 * energy);
+        Fate::Bound {
+            period: std::f32::consts::TAU * (semi_major.powi(3) / (GRAVITY * mass)).sqrt(),
+        }
+    }
+
+    /// Total acceleration at `pos`, summed over the planets in order.
+    pub fn acceleration_at(&self, pos: Vec3) -> Vec3 {
+        let mut acc = Vec3::ZERO;
+        for planet in &self.planets {
+            acc += acc_of(planet.mass, pos, planet.pos);
+        }
+        acc
+    }
+
+    pub fn integrate(&self, ball: &mut Ball, dt: f32) {
+        self.integrator.step(&mut ball.pos, &mut ball.vel, dt, &|pos| self.acceleration_at(pos));
+    }
+
+    /// Pushes the ball out of any planet it has sunk into and applies the
+    /// contact impulse: restitution along the normal and Coulomb friction
+    /// along the surface, both relative to the planet's own velocity.
+    pub fn collide(&self, ball: &mut Ball) -> Collision {
+        let mut result = Collision::None;
+
+        for (i, planet) in self.planets.iter().enumerate() {
+            let delta = ball.pos - planet.pos;
+
+            let needed_dist = ball.radius + planet.radius;
+
+            if delta.length() - needed_dist >= 0.0 {
+                continue;
+            }
+
+            let normal = delta.normalize();
+            ball.pos = planet.pos + normal * needed_dist;
+
+            let relative = ball.vel - planet.vel;
+            let normal_speed = relative.dot(normal);
+            if normal_speed > 0.0 {
+                // Already leaving, e.g. just launched.
+                continue;
+            }
+
+            let tangential = relative - normal * normal_speed;
+            let bounced = -normal_speed > BOUNCE_SPEED;
+            let new_normal_speed = if bounced {
+                -normal_speed * planet.restitution
+            } else {
+                0.0
+            };
+
+            // Friction can take away at most as much tangential speed as the
+            // normal impulse, scaled by the coefficient.
+            let impulse = new_normal_speed - normal_speed;
+            let tangential_speed = tangential.length();
+            let tangential = if tangential_speed > 0.0 {
+                tangential * (1.0 - (planet.friction * impulse / tangential_speed).min(1.0))
+            } else {
+                tangential
+            };
+
+            let relative = normal * new_normal_speed + tangential;
+            ball.vel = planet.vel + relative;
+
+            result = if bounced {
+                Collision::Bounced(i)
+            } else if relative.length() < REST_SPEED {
+                Collision::Landed(i)
+            } else {
+                Collision::Contact(i)
+            };
+        }
+
+        result
+    }
+
+    /// Whether the ball overlaps any planet, i.e. `collide` would bounce it.
+    pub fn touching(&self, ball: &Ball) -> bool {
+        self.planets
+            .iter()
+            .any(|planet| (ball.pos - planet.pos).length() < ball.radius + planet.radius)
+    }
+
+    /// Advances the planets and then every ball by `dt`. Balls that land stay
+    /// in `balls`, it's up to the caller to stop stepping them.
+    pub fn step(&mut self, dt: f32) -> Vec<Landing> {
+        self.advance(dt);
+
+        let mut balls = std::mem::take(&mut self.balls);
+        let mut landings = Vec::new();
+
+        for (i, ball) in balls.iter_mut().enumerate() {
+            self.integrate(ball, dt);
+            if let Collision::Landed(planet) = self.collide(ball) {
+                landings.push(Landing { ball: i, planet });
+            }
+        }
+
+        self.balls = balls;
+        landings
+    }
+
+    /// Follows `ball` for up to `n` steps of `dt`, exactly as `step` would,
+    /// until it comes to rest. The path stops at the first contact after
+    /// `max_bounces` bounces, but the ball is still followed to find `rest`.
+    pub fn predict(&self, ball: Ball, n: usize, dt: f32, max_bounces: usize) -> Trajectory {
+        let mut world = World {
+            planets: self.planets.clone(),
+            balls: Vec::new(),
+            ..*self
+        };
+        let mut ball = ball;
+        let mut trajectory = Trajectory::default();
+        let mut tracing = true;
+
+        for _ in 0..n {
+            world.advance(dt);
+            world.integrate(&mut ball, dt);
+            let collision = world.collide(&mut ball);
+
+            if let Collision::Bounced(_) = collision {
+                if trajectory.bounces.len() == max_bounces {
+                    tracing = false;
+                }
+            }
+
+            if tracing {
+                trajectory.points.push(ball.pos);
+                if let Collision::Bounced(_) = collision {
+                    trajectory.bounces.push(ball.pos);
+                }
+            }
+
+            if let Collision::Landed(_) = collision {
+                trajectory.rest = Some(ball.pos);
+                break;
+            }
+        }
+
+        trajectory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_planets() -> World {
+        World {
+            planets: vec![
+                Planet::new(Vec3::ZERO, 800.0, 1.3),
+                Planet::new(Vec3::new(3.5, 1.0, 1.5), 750.0, 0.9),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn predict_matches_step() {
+        let mut world = two_planets();
+        let ball = Ball::new(Vec3::new(0.0, 1.4, 0.0), Vec3::new(1.5, 2.0, 0.3), 0.1);
+        let dt = 1.0 / 240.0;
+
+        let predicted = world.predict(ball, 20_000, dt, usize::MAX);
+        assert!(!predicted.bounces.is_empty());
+
+        world.balls.push(ball);
+        for expected in &predicted.points {
+            world.step(dt);
+            assert_eq!(world.balls[0].pos, *expected);
+        }
+        assert_eq!(predicted.rest, Some(world.balls[0].pos));
+    }
+
+    #[test]
+    fn predict_stops_path_after_bounces() {
+        let world = two_planets();
+        let ball = Ball::new(Vec3::new(0.0, 1.4, 0.0), Vec3::new(1.5, 2.0, 0.3), 0.1);
+        let dt = 1.0 / 240.0;
+
+        let full = world.predict(ball, 20_000, dt, usize::MAX);
+        let first = world.predict(ball, 20_000, dt, 1);
+
+        assert_eq!(first.bounces, full.bounces[..1]);
+        assert!(first.points.len() < full.points.len());
+        assert_eq!(first.rest, full.rest);
+    }
+
+    #[test]
+    fn ball_lands_eventually() {
+        let mut world = two_planets();
+        world.balls.push(Ball::new(Vec3::new(0.0, 1.6, 0.0), Vec3::ZERO, 0.1));
+
+        let landed = (0..10_000).any(|_| !world.step(1.0 / 240.0).is_empty());
+        assert!(landed);
+    }
+
+    #[test]
+    fn predict_matches_step_with_orbits() {
+        let mut world = two_planets();
+        world.planets[1].motion = Motion::Orbit(Orbit {
+            parent: 0,
+            semi_major: 4.0,
+            eccentricity: 0.3,
+            periapsis: Vec3::X,
+            normal: Vec3::Y,
+            phase: 0.0,
+        });
+        world.update_planets();
+
+        let ball = Ball::new(Vec3::new(0.0, 1.4, 0.0), Vec3::new(1.5, 2.0, 0.3), 0.1);
+        let dt = 1.0 / 240.0;
+        let predicted = world.predict(ball, 20_000, dt, usize::MAX);
+
+        world.balls.push(ball);
+        for expected in &predicted.points {
+            world.step(dt);
+            assert_eq!(world.balls[0].pos, *expected);
+        }
+    }
+
+    #[test]
+    fn ball_rests_on_moving_planet() {
+        let mut world = World {
+            planets: vec![Planet {
+                motion: Motion::Path(Path {
+                    points: vec![Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0)],
+                    period: 20.0,
+                }),
+                ..Planet::new(Vec3::ZERO, 800.0, 1.0)
+            }],
+            ..Default::default()
+        };
+        world.update_planets();
+        let vel = world.planets[0].vel;
+        world.balls.push(Ball::new(Vec3::new(0.0, 1.1, 0.0), vel, 0.1));
+
+        let landed = (0..10_000).any(|_| !world.step(1.0 / 240.0).is_empty());
+        assert!(landed);
+        assert!((world.balls[0].vel - world.planets[0].vel).length() < REST_SPEED);
+    }
+
+    /// A moon going around a planet, with a third body further out.
+    fn cluster(n_body: NBody) -> World {
+        let free = |pos, vel, mass| Planet {
+            vel,
+            motion: Motion::Free,
+            ..Planet::new(pos, mass, 0.5)
+        };
+        World {
+            planets: vec![
+                free(Vec3::ZERO, Vec3::new(0.0, 0.0, -0.5), 900.0),
+                free(Vec3::new(5.0, 0.0, 0.0), Vec3::new(0.0, 0.3, 3.8), 300.0),
+                free(Vec3::new(-12.0, 0.0, 0.0), Vec3::new(0.0, 0.3, -2.7), 150.0),
+            ],
+            n_body: Some(n_body),
+            ..Default::default()
+        }
+    }
+
+    fn momentum(world: &World) -> Vec3 {
+        world.planets.iter().fold(Vec3::ZERO, |sum, planet| sum + planet.mass * planet.vel)
+    }
+
+    fn energy(world: &World) -> f32 {
+        let mut energy = 0.0;
+        for (i, a) in world.planets.iter().enumerate() {
+            energy += 0.5 * a.mass * a.vel.length_squared();
+            for b in &world.planets[i + 1..] {
+                energy -= GRAVITY * a.mass * b.mass / (a.pos - b.pos).length();
+            }
+        }
+        energy
+    }
+
+    #[test]
+    fn n_body_conserves_momentum_and_energy() {
+        let mut world = cluster(NBody::default());
+        let (p0, e0) = (momentum(&world), energy(&world));
+
+        for _ in 0..4800 {
+            world.advance(1.0 / 240.0);
+        }
+
+        let scale = world.planets.iter().map(|planet| planet.mass * planet.vel.length()).sum::<f32>();
+        assert!((momentum(&world) - p0).length() < 1e-3 * scale);
+        assert!(((energy(&world) - e0) / e0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn barnes_hut_conserves_momentum_and_energy() {
+        let mut world = cluster(NBody {
+            direct_below: 0,
+            ..Default::default()
+        });
+        let (p0, e0) = (momentum(&world), energy(&world));
+
+        for _ in 0..4800 {
+            world.advance(1.0 / 240.0);
+        }
+
+        let scale = world.planets.iter().map(|planet| planet.mass * planet.vel.length()).sum::<f32>();
+        assert!((momentum(&world) - p0).length() < 1e-2 * scale);
+        assert!(((energy(&world) - e0) / e0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn free_planets_drift_without_n_body() {
+        let mut world = cluster(NBody::default());
+        world.n_body = None;
+        let start = world.planets[1].clone();
+
+        world.advance(2.0);
+        assert_eq!(world.planets[1].vel, start.vel);
+        assert!((world.planets[1].pos - (start.pos + start.vel * 2.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn replay_from_scratch_matches_live_steps() {
+        let dt = 1.0 / 240.0;
+        let mut live = cluster(NBody::default());
+        let mut replay = live.clone();
+
+        for _ in 0..500 {
+            live.advance(dt);
+        }
+        let ball = Ball::new(live.planets[0].pos + Vec3::Y * 0.6, Vec3::new(1.0, 1.5, 0.2), 0.1);
+        live.balls.push(ball);
+
+        for _ in 0..500 {
+            replay.advance(dt);
+        }
+        let predicted = replay.predict(ball, 2000, dt, usize::MAX);
+        assert!(!predicted.points.is_empty());
+
+        for expected in &predicted.points {
+            live.step(dt);
+            assert_eq!(live.balls[0].pos, *expected);
+        }
+    }
+
+    #[test]
+    fn fate_of_escaping_and_orbiting_balls() {
+        let world = World {
+            planets: vec![Planet::new(Vec3::ZERO, 800.0, 1.0)],
+            ..Default::default()
+        };
+        let pos = Vec3::new(3.0, 0.0, 0.0);
+        let circular = (GRAVITY * 800.0 / 3.0).sqrt();
+
+        let orbit = Orbit {
+            parent: 0,
+            semi_major: 3.0,
+            eccentricity: 0.0,
+            periapsis: Vec3::X,
+            normal: Vec3::Y,
+            phase: 0.0,
+        };
+        match world.fate(&Ball::new(pos, Vec3::new(0.0, 0.0, circular), 0.1)) {
+            Fate::Bound { period } => assert!((period - orbit.period(800.0)).abs() < 1e-3),
+            Fate::Escaping => panic!("circular orbit counted as escaping"),
+        }
+
+        let escape = circular * 2f32.sqrt() * 1.01;
+        assert_eq!(world.fate(&Ball::new(pos, Vec3::new(escape, 0.0, 0.0), 0.1)), Fate::Escaping);
+        assert_ne!(world.fate(&Ball::new(pos, Vec3::new(-escape, 0.0, 0.0), 0.1)), Fate::Escaping);
+    }
+
+    #[test]
+    fn bounds_cover_every_planet() {
+        let world = two_planets();
+        let (centre, radius) = world.bounds();
+        for planet in &world.planets {
+            assert!((planet.pos - centre).length() + planet.radius <= radius + 1e-5);
+        }
+    }
+
+    #[test]
+    fn restitution_scales_bounce() {
+        let mut world = two_planets();
+        world.planets[0].restitution = 0.5;
+
+        let mut ball = Ball::new(Vec3::new(0.0, 1.35, 0.0), Vec3::new(0.0, -2.0, 0.0), 0.1);
+        assert_eq!(world.collide(&mut ball), Collision::Bounced(0));
+        assert_eq!(ball.vel, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn friction_stops_rolling_ball() {
+        let mut world = World {
+            planets: vec![Planet::new(Vec3::ZERO, 800.0, 1.0)],
+            ..Default::default()
+        };
+        world.balls.push(Ball::new(Vec3::new(0.0, 1.1, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.1));
+
+        let dt = 1.0 / 240.0;
+        let landed = (0..10_000).any(|_| !world.step(dt).is_empty());
+        assert!(landed);
+
+        // It rolled along the surface rather than bouncing off it.
+        let ball = world.balls[0];
+        assert!((ball.pos.length() - 1.1).abs() < 1e-4);
+        assert!(ball.pos.x > 0.0);
+    }
+}
@@ -0,0 +1,157 @@
+use glam::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::GRAVITY;
+
+/// How a planet moves over time.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Motion {
+    #[default]
+    Static,
+    Orbit(Orbit),
+    Path(Path),
+    /// Moved only by its velocity and, in n-body mode, the other planets.
+    Free,
+}
+
+/// A Keplerian orbit around another planet, which must come earlier in the
+/// planet list.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Orbit {
+    pub parent: usize,
+    pub semi_major: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eccentricity: f32,
+    /// Direction from the parent to the closest point of the orbit.
+    #[cfg_attr(feature = "serde", serde(default = "Orbit::default_periapsis"))]
+    pub periapsis: Vec3,
+    /// Normal of the orbital plane, the planet goes anticlockwise around it.
+    #[cfg_attr(feature = "serde", serde(default = "Orbit::default_normal"))]
+    pub normal: Vec3,
+    /// Mean anomaly at time zero, in radians.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub phase: f32,
+}
+
+impl Orbit {
+    #[cfg(feature = "serde")]
+    fn default_periapsis() -> Vec3 {
+        Vec3::X
+    }
+
+    #[cfg(feature = "serde")]
+    fn default_normal() -> Vec3 {
+        Vec3::Y
+    }
+
+    pub fn period(&self, parent_mass: f32) -> f32 {
+        std::f32::consts::TAU * (self.semi_major.powi(3) / (GRAVITY * parent_mass)).sqrt()
+    }
+
+    /// Position and velocity relative to the parent at `time`.
+    pub fn state_at(&self, parent_mass: f32, time: f32) -> (Vec3, Vec3) {
+        let e = self.eccentricity;
+        let mean_motion = std::f32::consts::TAU / self.period(parent_mass);
+        let mean_anomaly = self.phase + mean_motion * time;
+
+        // Newton's method on Kepler's equation, M = E - e sin E.
+        let mut ecc_anomaly = if e < 0.8 { mean_anomaly } else { std::f32::consts::PI };
+        for _ in 0..8 {
+            ecc_anomaly -= (ecc_anomaly - e * ecc_anomaly.sin() - mean_anomaly)
+                / (1.0 - e * ecc_anomaly.cos());
+        }
+
+        let (sin, cos) = ecc_anomaly.sin_cos();
+        let minor = (1.0 - e * e).sqrt();
+        let rate = mean_motion / (1.0 - e * cos);
+
+        let normal = self.normal.normalize();
+        let p = (self.periapsis - normal * normal.dot(self.periapsis)).normalize();
+        let q = normal.cross(p);
+
+        let pos = self.semi_major * ((cos - e) * p + minor * sin * q);
+        let vel = self.semi_major * rate * (-sin * p + minor * cos * q);
+        (pos, vel)
+    }
+}
+
+/// A closed loop through `points`, taking `period` seconds to go around.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Path {
+    pub points: Vec<Vec3>,
+    pub period: f32,
+}
+
+impl Path {
+    /// Position and velocity on a Catmull-Rom spline through the points.
+    pub fn state_at(&self, time: f32) -> (Vec3, Vec3) {
+        let n = self.points.len();
+        if n == 0 {
+            return (Vec3::ZERO, Vec3::ZERO);
+        }
+        if n == 1 || self.period <= 0.0 {
+            return (self.points[0], Vec3::ZERO);
+        }
+
+        let along = (time / self.period).rem_euclid(1.0) * n as f32;
+        let i = (along as usize).min(n - 1);
+        let t = along - i as f32;
+
+        let p0 = self.points[(i + n - 1) % n];
+        let p1 = self.points[i];
+        let p2 = self.points[(i + 1) % n];
+        let p3 = self.points[(i + 2) % n];
+
+        let (t2, t3) = (t * t, t * t * t);
+        let pos = 0.5 * ((2.0 * p1)
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+        let dpos_dt = 0.5 * ((p2 - p0)
+            + 2.0 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t
+            + 3.0 * (3.0 * p1 - p0 - 3.0 * p2 + p3) * t2);
+
+        (pos, dpos_dt * n as f32 / self.period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circular_orbit_keeps_its_radius() {
+        let orbit = Orbit {
+            parent: 0,
+            semi_major: 3.0,
+            eccentricity: 0.0,
+            periapsis: Vec3::X,
+            normal: Vec3::Y,
+            phase: 0.0,
+        };
+
+        let period = orbit.period(800.0);
+        for i in 0..16 {
+            let (pos, vel) = orbit.state_at(800.0, period * i as f32 / 16.0);
+            assert!((pos.length() - 3.0).abs() < 1e-4);
+            assert!(pos.dot(vel).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn path_velocity_matches_position() {
+        let path = Path {
+            points: vec![Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 2.0)],
+            period: 6.0,
+        };
+
+        let h = 1e-3;
+        let (a, _) = path.state_at(1.3 - h);
+        let (b, vel) = path.state_at(1.3 + h);
+        assert!(((b - a) / (2.0 * h) - vel).length() < 1e-2);
+    }
+}
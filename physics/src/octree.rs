@@ -0,0 +1,223 @@
+//! Barnes-Hut octree for approximating gravity from many bodies at once.
+
+use glam::Vec3;
+
+use crate::acc_of;
+
+/// Leaves this small stop splitting, so coincident bodies can't recurse
+/// forever.
+const MIN_HALF_SIZE: f32 = 1e-4;
+
+#[derive(Clone, Debug)]
+struct Node {
+    center: Vec3,
+    half_size: f32,
+    /// Signed total mass, which is what pulls.
+    mass: f32,
+    /// Sum of `|mass|`, which weights `centre_of_mass` so negative masses
+    /// don't drag it off somewhere meaningless.
+    weight: f32,
+    centre_of_mass: Vec3,
+    /// Index of the first of eight consecutive children.
+    children: Option<usize>,
+    /// Bodies stored directly in this leaf.
+    bodies: Vec<usize>,
+}
+
+impl Node {
+    fn new(center: Vec3, half_size: f32) -> Self {
+        Self {
+            center,
+            half_size,
+            mass: 0.0,
+            weight: 0.0,
+            centre_of_mass: Vec3::ZERO,
+            children: None,
+            bodies: Vec::new(),
+        }
+    }
+
+    fn octant(&self, pos: Vec3) -> usize {
+        (pos.x >= self.center.x) as usize
+            | ((pos.y >= self.center.y) as usize) << 1
+            | ((pos.z >= self.center.z) as usize) << 2
+    }
+}
+
+pub struct Octree<'a> {
+    nodes: Vec<Node>,
+    bodies: &'a [(Vec3, f32)],
+}
+
+impl<'a> Octree<'a> {
+    /// Builds a tree over `(position, mass)` pairs.
+    pub fn new(bodies: &'a [(Vec3, f32)]) -> Self {
+        let (min, max) = bodies.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), (pos, _)| (min.min(*pos), max.max(*pos)),
+        );
+        let (center, half_size) = if bodies.is_empty() {
+            (Vec3::ZERO, 1.0)
+        } else {
+            ((min + max) * 0.5, ((max - min).max_element() * 0.5).max(MIN_HALF_SIZE))
+        };
+
+        let mut tree = Self {
+            nodes: vec![Node::new(center, half_size)],
+            bodies,
+        };
+        for i in 0..bodies.len() {
+            tree.insert(0, i);
+        }
+        tree.summarise(0);
+        tree
+    }
+
+    fn insert(&mut self, node: usize, body: usize) {
+        if let Some(first) = self.nodes[node].children {
+            let octant = self.nodes[node].octant(self.bodies[body].0);
+            self.insert(first + octant, body);
+            return;
+        }
+
+        if self.nodes[node].bodies.is_empty() || self.nodes[node].half_size <= MIN_HALF_SIZE {
+            self.nodes[node].bodies.push(body);
+            return;
+        }
+
+        // Split the leaf and push its bodies down.
+        let first = self.nodes.len();
+        let (center, half) = (self.nodes[node].center, self.nodes[node].half_size * 0.5);
+        for octant in 0..8 {
+            let offset = Vec3::new(
+                if octant & 1 != 0 { half } else { -half },
+                if octant & 2 != 0 { half } else { -half },
+                if octant & 4 != 0 { half } else { -half },
+            );
+            self.nodes.push(Node::new(center + offset, half));
+        }
+        self.nodes[node].children = Some(first);
+
+        for old in std::mem::take(&mut self.nodes[node].bodies) {
+            self.insert(node, old);
+        }
+        self.insert(node, body);
+    }
+
+    fn summarise(&mut self, node: usize) {
+        let (mut mass, mut weight, mut weighted) = (0.0, 0.0, Vec3::ZERO);
+
+        if let Some(first) = self.nodes[node].children {
+            for child in first..first + 8 {
+                self.summarise(child);
+                let child = &self.nodes[child];
+                mass += child.mass;
+                weight += child.weight;
+                weighted += child.centre_of_mass * child.weight;
+            }
+        } else {
+            for &body in &self.nodes[node].bodies {
+                let (pos, m) = self.bodies[body];
+                mass += m;
+                weight += m.abs();
+                weighted += pos * m.abs();
+            }
+        }
+
+        let node = &mut self.nodes[node];
+        node.mass = mass;
+        node.weight = weight;
+        node.centre_of_mass = if weight > 0.0 { weighted / weight } else { node.center };
+    }
+
+    /// Acceleration at `pos`, leaving out body `skip` so a body doesn't pull
+    /// on itself. Cells smaller than `theta` times their distance are treated
+    /// as a single mass.
+    pub fn acceleration_at(&self, pos: Vec3, skip: Option<usize>, theta: f32) -> Vec3 {
+        let mut acc = Vec3::ZERO;
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if node.weight == 0.0 {
+                continue;
+            }
+
+            match node.children {
+                Some(first) => {
+                    let dist = (node.centre_of_mass - pos).length();
+                    let contains_skip = skip.is_some_and(|skip| {
+                        let body = self.bodies[skip].0;
+                        (body - node.center).abs().max_element() <= node.half_size
+                    });
+                    if !contains_skip && 2.0 * node.half_size < theta * dist {
+                        acc += acc_of(node.mass, pos, node.centre_of_mass);
+                    } else {
+                        stack.extend(first..first + 8);
+                    }
+                }
+                None => {
+                    for &body in &node.bodies {
+                        if Some(body) != skip {
+                            let (body_pos, mass) = self.bodies[body];
+                            acc += acc_of(mass, pos, body_pos);
+                        }
+                    }
+                }
+            }
+        }
+
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic generator so the test doesn't need `rand`.
+    fn lcg(state: &mut u32) -> f32 {
+        *state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*state >> 8) as f32 / (1 << 24) as f32
+    }
+
+    #[test]
+    fn approximates_direct_sum() {
+        let mut state = 7;
+        let bodies = (0..300)
+            .map(|_| {
+                let pos = Vec3::new(lcg(&mut state), lcg(&mut state), lcg(&mut state)) * 40.0;
+                (pos, 50.0 + 100.0 * lcg(&mut state))
+            })
+            .collect::<Vec<_>>();
+
+        let tree = Octree::new(&bodies);
+        for i in (0..bodies.len()).step_by(17) {
+            let pos = bodies[i].0;
+            let direct = bodies
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(Vec3::ZERO, |acc, (_, (p, m))| acc + acc_of(*m, pos, *p));
+            let approx = tree.acceleration_at(pos, Some(i), 0.5);
+
+            assert!((approx - direct).length() < 0.05 * direct.length());
+        }
+    }
+
+    #[test]
+    fn exact_with_zero_theta() {
+        let bodies = [
+            (Vec3::ZERO, 100.0),
+            (Vec3::new(3.0, 0.0, 0.0), 50.0),
+            (Vec3::new(0.0, 2.0, 1.0), -20.0),
+        ];
+        let tree = Octree::new(&bodies);
+
+        let pos = Vec3::new(1.0, 1.0, 1.0);
+        let direct = bodies
+            .iter()
+            .fold(Vec3::ZERO, |acc, (p, m)| acc + acc_of(*m, pos, *p));
+        assert!((tree.acceleration_at(pos, None, 0.0) - direct).length() < 1e-5);
+    }
+}
@@ -0,0 +1,252 @@
+//! Brute force search for ways to finish a level, by sweeping launch
+//! velocities and following each shot exactly as the game would.
+
+use glam::Vec3;
+use crate::{Ball, Collision, World, GOAL_REACH, OUT_OF_BOUNDS_MARGIN, OUT_OF_BOUNDS_SCALE};
+
+/// How thoroughly `solve` searches.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sweep {
+    /// Launch directions tried, spread evenly over the sphere. The ones
+    /// pointing into the planet are skipped.
+    pub directions: usize,
+    /// Launch speeds tried in each direction, evenly up to `max_speed`.
+    pub speeds: usize,
+    pub max_speed: f32,
+    pub max_strokes: u32,
+    /// How many of the places shots came to rest are tried from for the
+    /// next stroke, closest to the goal first.
+    pub frontier: usize,
+    /// Solutions kept for the report.
+    pub examples: usize,
+    /// Longest a shot is followed, in steps of `dt`.
+    pub steps: usize,
+    pub dt: f32,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            directions: 96,
+            speeds: 12,
+            max_speed: 8.0,
+            max_strokes: 3,
+            frontier: 8,
+            examples: 3,
+            steps: 20 * 240,
+            dt: 1.0 / 240.0,
+        }
+    }
+}
+
+/// A point on a planet's surface, `offset` from its centre.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spot {
+    pub planet: usize,
+    pub offset: Vec3,
+}
+
+/// One shot of a solution.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stroke {
+    /// Where the ball was launched from.
+    pub from: Vec3,
+    /// Launch velocity relative to the planet the ball was on, i.e. what the
+    /// player aims.
+    pub vel: Vec3,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// Fewest strokes any solution found took.
+    pub min_strokes: Option<u32>,
+    /// Up to `Sweep::examples` solutions with `min_strokes` strokes.
+    pub solutions: Vec<Vec<Stroke>>,
+    /// Shots followed in total.
+    pub shots: usize,
+}
+
+impl Report {
+    pub fn reachable(&self) -> bool {
+        self.min_strokes.is_some()
+    }
+}
+
+enum Outcome {
+    Reached,
+    Rested(Spot, World),
+    Lost,
+}
+
+/// A place to shoot from, with the world as it is when the ball gets there
+/// and the strokes it took.
+struct Node {
+    spot: Spot,
+    world: World,
+    strokes: Vec<Stroke>,
+}
+
+/// Searches for shots from `start` that reach `goal`, trying every stroke
+/// count up to `Sweep::max_strokes` until one works. Each shot is fired the
+/// moment the last one comes to rest.
+pub fn solve(world: &World, start: Spot, goal: Spot, ball_radius: f32, sweep: &Sweep) -> Report {
+    let mut report = Report::default();
+    let mut frontier = vec![Node {
+        spot: start,
+        world: world.clone(),
+        strokes: Vec::new(),
+    }];
+    let directions = fibonacci_sphere(sweep.directions);
+
+    for stroke in 1..=sweep.max_strokes {
+        let mut rests: Vec<Node> = Vec::new();
+
+        for node in &frontier {
+            let normal = node.spot.offset.normalize_or_zero();
+            let from = node.world.planets[node.spot.planet].pos + node.spot.offset;
+
+            for dir in directions.iter().filter(|dir| dir.dot(normal) > -0.2) {
+                for i in 1..=sweep.speeds {
+                    let vel = *dir * sweep.max_speed * i as f32 / sweep.speeds as f32;
+                    report.shots += 1;
+
+                    let mut strokes = node.strokes.clone();
+                    strokes.push(Stroke { from, vel });
+
+                    match shoot(&node.world, node.spot, vel, goal, ball_radius, sweep) {
+                        Outcome::Reached => {
+                            report.min_strokes = Some(stroke);
+                            if report.solutions.len() < sweep.examples {
+                                report.solutions.push(strokes);
+                            }
+                        }
+                        Outcome::Rested(spot, world) => {
+                            // Many shots end up in the same place, only the
+                            // first one there is worth shooting from again.
+                            let seen = rests.iter().any(|rest| {
+                                rest.spot.planet == spot.planet && (rest.spot.offset - spot.offset).length() < 0.25
+                            });
+                            if !seen {
+                                rests.push(Node { spot, world, strokes });
+                            }
+                        }
+                        Outcome::Lost => {}
+                    }
+                }
+            }
+        }
+
+        if report.reachable() {
+            break;
+        }
+
+        let distance = |node: &Node| {
+            let goal = node.world.planets[goal.planet].pos + goal.offset;
+            (node.world.planets[node.spot.planet].pos + node.spot.offset - goal).length()
+        };
+        rests.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        rests.truncate(sweep.frontier);
+        frontier = rests;
+    }
+
+    report
+}
+
+/// Follows one shot the way the game steps the ball, until it reaches the
+/// goal, comes to rest or goes out of bounds.
+fn shoot(world: &World, spot: Spot, vel: Vec3, goal: Spot, ball_radius: f32, sweep: &Sweep) -> Outcome {
+    let mut world = world.clone();
+    let planet = &world.planets[spot.planet];
+    let mut ball = Ball::new(planet.pos + spot.offset, planet.vel + vel, ball_radius);
+    let (centre, radius) = world.bounds();
+    let limit = radius * OUT_OF_BOUNDS_SCALE + OUT_OF_BOUNDS_MARGIN;
+
+    for _ in 0..sweep.steps {
+        world.advance(sweep.dt);
+        world.integrate(&mut ball, sweep.dt);
+        let collision = world.collide(&mut ball);
+
+        let target = world.planets[goal.planet].pos + goal.offset;
+        if (ball.pos - target).length() < GOAL_REACH {
+            return Outcome::Reached;
+        }
+        if let Collision::Landed(planet) = collision {
+            let offset = ball.pos - world.planets[planet].pos;
+            return Outcome::Rested(Spot { planet, offset }, world);
+        }
+        if (ball.pos - centre).length() > limit {
+            return Outcome::Lost;
+        }
+    }
+
+    Outcome::Lost
+}
+
+/// `n` directions spread close to evenly over the unit sphere.
+fn fibonacci_sphere(n: usize) -> Vec<Vec3> {
+    let golden = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+    (0..n)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / n as f32;
+            let ring = (1.0 - y * y).sqrt();
+            let angle = golden * i as f32;
+            Vec3::new(ring * angle.cos(), y, ring * angle.sin())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Planet;
+
+    fn one_planet() -> World {
+        World {
+            planets: vec![Planet::new(Vec3::ZERO, 800.0, 1.3)],
+            ..Default::default()
+        }
+    }
+
+    fn on_surface(dir: Vec3) -> Spot {
+        Spot {
+            planet: 0,
+            offset: dir.normalize() * 1.4,
+        }
+    }
+
+    #[test]
+    fn finds_a_hop_around_the_planet() {
+        let report = solve(&one_planet(), on_surface(Vec3::Y), on_surface(Vec3::X), 0.1, &Sweep::default());
+
+        assert_eq!(report.min_strokes, Some(1));
+        assert!(!report.solutions.is_empty());
+        for solution in &report.solutions {
+            assert_eq!(solution.len(), 1);
+        }
+    }
+
+    #[test]
+    fn too_slow_to_get_anywhere() {
+        let sweep = Sweep {
+            directions: 24,
+            speeds: 3,
+            max_speed: 0.05,
+            max_strokes: 2,
+            ..Default::default()
+        };
+        let report = solve(&one_planet(), on_surface(Vec3::Y), on_surface(-Vec3::Y), 0.1, &sweep);
+
+        assert!(!report.reachable());
+        assert!(report.solutions.is_empty());
+        assert!(report.shots > 0);
+    }
+
+    #[test]
+    fn directions_cover_the_sphere() {
+        let directions = fibonacci_sphere(200);
+        for axis in [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z] {
+            assert!(directions.iter().any(|dir| dir.dot(axis) > 0.95));
+        }
+        assert!(directions.iter().all(|dir| (dir.length() - 1.0).abs() < 1e-5));
+    }
+}
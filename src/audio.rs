@@ -20,7 +20,9 @@ impl Plugin for GameAudioPlugin {
             .add_startup_system_to_stage(StartupStage::PreStartup, load_audio)
             .add_startup_system(start_intro_music)
             .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(start_intro_music))
-            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_bgm_music));
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_bgm_music))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_bgm_music))
+            .add_system_set(SystemSet::on_resume(GameState::Game).with_system(resume_bgm_music));
     }
 }
 
@@ -37,6 +39,10 @@ fn resume_bgm_music(audio: Res<Audio>, audio_state: Res<AudioState>){
     audio.resume_channel(&audio_state.bgm_channel);
 }
 
+fn pause_bgm_music(audio: Res<Audio>, audio_state: Res<AudioState>){
+    audio.pause_channel(&audio_state.bgm_channel);
+}
+
 fn start_intro_music(audio: Res<Audio>, audio_state: Res<AudioState>){
     audio.pause_channel(&audio_state.bgm_channel);
     audio.play_looped_in_channel(audio_state.intro_handle.clone(), &audio_state.intro_channel);
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use jamhacks_physics::solve::{Report, Sweep};
+use crate::levels::{game_sweep, Level, SavedLevelPack};
+
+/// Run with `cargo run -- --check-levels [files...]`. Checks the given
+/// level files, or every level in the main pack, and prints whether each can
+/// be finished, in how few strokes, and a few ways to do it. Returns whether
+/// they all can.
+pub fn check_levels(files: &[String]) -> bool {
+    let paths = if files.is_empty() {
+        match pack_levels() {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("Couldn't read the level pack: {}", err);
+                return false;
+            }
+        }
+    } else {
+        files.iter().map(PathBuf::from).collect()
+    };
+
+    let sweep = game_sweep();
+    let mut all_solvable = true;
+    for path in paths {
+        let level = match read_level(&path) {
+            Ok(level) => level,
+            Err(err) => {
+                println!("{}: couldn't read: {}", path.display(), err);
+                all_solvable = false;
+                continue;
+            }
+        };
+
+        let report = level.solve(&sweep);
+        print_report(&level, &path, &report, &sweep);
+        all_solvable &= report.reachable();
+    }
+    all_solvable
+}
+
+fn levels_dir() -> PathBuf {
+    bevy::asset::FileAssetIo::get_root_path().join("assets").join("levels")
+}
+
+fn pack_levels() -> Result<Vec<PathBuf>, anyhow::Error> {
+    let dir = levels_dir();
+    let pack = ron::de::from_str::<SavedLevelPack>(&fs::read_to_string(dir.join("main.pack.ron"))?)?;
+    Ok(pack.levels.iter().map(|file| dir.join(file)).collect())
+}
+
+fn read_level(path: &Path) -> Result<Level, anyhow::Error> {
+    let level = ron::de::from_str::<Level>(&fs::read_to_string(path)?)?;
+    level.validate()?;
+    Ok(level)
+}
+
+fn print_report(level: &Level, path: &Path, report: &Report, sweep: &Sweep) {
+    let file = path.file_name().map_or(path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    match report.min_strokes {
+        Some(strokes) => println!(
+            "{} ({}): solvable in {} stroke(s), par {}{}, {} shots tried",
+            level.name,
+            file,
+            strokes,
+            level.par,
+            if strokes > level.par { ", over par" } else { "" },
+            report.shots
+        ),
+        None => println!(
+            "{} ({}): NOT solvable in {} stroke(s), {} shots tried",
+            level.name, file, sweep.max_strokes, report.shots
+        ),
+    }
+
+    for (i, solution) in report.solutions.iter().enumerate() {
+        println!("  solution {}:", i + 1);
+        for stroke in solution {
+            println!(
+                "    from ({:.2}, {:.2}, {:.2}) launch at ({:.2}, {:.2}, {:.2})",
+                stroke.from.x, stroke.from.y, stroke.from.z, stroke.vel.x, stroke.vel.y, stroke.vel.z
+            );
+        }
+    }
+}
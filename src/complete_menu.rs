@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use crate::levels::{go_to_level, next_level, CurrentLevel, LevelPack, Levels};
+use crate::score::{format_time, relative_to_par, Scorecard};
+use crate::setup::*;
+use crate::start_menu::{spawn_panel, UiAssets};
+
+/// The screens shown over the finished level, after reaching the target.
+pub struct CompleteMenuPlugin;
+
+impl Plugin for CompleteMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(GameState::LevelComplete).with_system(add_level_complete))
+            .add_system_set(SystemSet::on_update(GameState::LevelComplete).with_system(handle_complete_buttons))
+            .add_system_set(SystemSet::on_exit(GameState::LevelComplete).with_system(crate::despawn::<CompleteMenu>))
+            .add_system_set(SystemSet::on_enter(GameState::GameComplete).with_system(add_game_complete))
+            .add_system_set(SystemSet::on_update(GameState::GameComplete).with_system(handle_complete_buttons))
+            .add_system_set(SystemSet::on_exit(GameState::GameComplete).with_system(crate::despawn::<CompleteMenu>));
+    }
+}
+
+#[derive(Component)]
+struct CompleteMenu;
+
+#[derive(Component, Copy, Clone)]
+enum CompleteButton {
+    NextLevel,
+    Retry,
+    MainMenu,
+}
+
+fn add_level_complete(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    ui_assets: Res<UiAssets>,
+    scorecard: Res<Scorecard>,
+) {
+    let body = match scorecard.last() {
+        Some(score) => format!(
+            "{} strokes, par {} ({})\nTime {}",
+            score.strokes,
+            score.par,
+            relative_to_par(score.strokes, score.par),
+            format_time(score.time)
+        ),
+        None => String::new(),
+    };
+
+    spawn_panel(
+        &mut commands,
+        &assets,
+        &ui_assets,
+        CompleteMenu,
+        "Level complete",
+        body,
+        &[("Next level", CompleteButton::NextLevel), ("Retry", CompleteButton::Retry)],
+    );
+}
+
+fn add_game_complete(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    ui_assets: Res<UiAssets>,
+    scorecard: Res<Scorecard>,
+) {
+    spawn_panel(
+        &mut commands,
+        &assets,
+        &ui_assets,
+        CompleteMenu,
+        "All levels complete",
+        scorecard.summary(),
+        &[("Main menu", CompleteButton::MainMenu), ("Retry", CompleteButton::Retry)],
+    );
+}
+
+fn handle_complete_buttons(
+    buttons: Query<(&Interaction, &CompleteButton), Changed<Interaction>>,
+    mut game: ResMut<State<GameState>>,
+    mut level: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    mut scorecard: ResMut<Scorecard>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            CompleteButton::NextLevel => {
+                game.pop().unwrap();
+                next_level(&mut current, levels.len(&packs), &mut level);
+            }
+            CompleteButton::Retry => {
+                scorecard.retry();
+                game.pop().unwrap();
+                let index = current.0;
+                go_to_level(index, &mut current, &mut level);
+            }
+            CompleteButton::MainMenu => {
+                game.replace(GameState::Splash).unwrap();
+                level.set(LevelState::None).unwrap();
+            }
+        }
+        return;
+    }
+}
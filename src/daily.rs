@@ -0,0 +1,211 @@
+use std::fmt;
+use bevy::prelude::*;
+use instant::{Duration, SystemTime};
+use crate::levels::{CurrentLevel, Level, Levels};
+use crate::progress::Progress;
+use crate::random_level::{Generated, Generator, Requester};
+use crate::score::Scorecard;
+use crate::setup::*;
+use crate::start_menu::{set_button_label, start_game};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Adds "Daily challenge" to the main menu: a generated level made from
+/// today's date in UTC, so everyone gets the same one on the same day. It
+/// gets harder through the week, easiest on Monday and hardest on Sunday.
+/// The best result on it is saved, until the next day's comes along. Needs
+/// `RandomLevelPlugin`, which makes the level.
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Daily>()
+            .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(stop_daily))
+            .add_system_set(SystemSet::on_update(GameState::Splash)
+                .with_system(handle_daily_button)
+                .with_system(play_daily)
+                .with_system(reset_daily_button)
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameComplete).with_system(record_daily));
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub struct DailyButton;
+
+/// A day in UTC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Date {
+    /// Days since 1970-01-01.
+    days: u64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        Self {
+            days: since_epoch.as_secs() / SECONDS_PER_DAY,
+        }
+    }
+
+    pub fn seed(self) -> u64 {
+        self.days
+    }
+
+    /// From 0 on Mondays to 1 on Sundays.
+    pub fn difficulty(self) -> f32 {
+        // 1970-01-01 was a Thursday.
+        let weekday = (self.days + 3) % 7;
+        weekday as f32 / 6.0
+    }
+
+    /// Year, month and day, from Howard Hinnant's `civil_from_days`.
+    fn civil(self) -> (i64, u32, u32) {
+        let z = self.days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + (month <= 2) as i64;
+        (year, month, day)
+    }
+}
+
+/// Like 2022-05-14, which is also what the best result is saved under.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.civil();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+#[derive(Default)]
+struct Daily {
+    /// The last challenge made, so playing it again the same day doesn't
+    /// wait for the solver.
+    made: Option<(Date, Handle<Level>)>,
+    /// The day of the challenge being played, if it is being played.
+    playing: Option<Date>,
+}
+
+/// What the daily button says, with today's best if there is one.
+pub(crate) fn daily_label(progress: &Progress) -> String {
+    match progress.daily_best(&Date::today().to_string()) {
+        Some(best) => format!("Daily: best {}", best.strokes),
+        None => "Daily challenge".to_string(),
+    }
+}
+
+fn stop_daily(mut daily: ResMut<Daily>) {
+    daily.playing = None;
+}
+
+fn handle_daily_button(
+    buttons: Query<(&Interaction, &Children), (Changed<Interaction>, With<DailyButton>)>,
+    images: Query<&Children, With<UiImage>>,
+    mut texts: Query<&mut Text>,
+    daily: Res<Daily>,
+    mut generator: ResMut<Generator>,
+    mut generated: EventWriter<Generated>,
+) {
+    for (interaction, children) in buttons.iter() {
+        if *interaction != Interaction::Clicked || generator.is_busy() {
+            continue;
+        }
+
+        let date = Date::today();
+        let requester = Requester::Daily(date);
+        match &daily.made {
+            Some((made, level)) if *made == date => generated.send(Generated {
+                requester,
+                level: Some(level.clone()),
+            }),
+            _ => {
+                generator.start(requester, date.seed(), date.difficulty());
+                set_button_label(children, &images, &mut texts, "Generating...");
+            }
+        }
+    }
+}
+
+fn play_daily(
+    mut generated: EventReader<Generated>,
+    mut daily: ResMut<Daily>,
+    mut levels: ResMut<Levels>,
+    mut game_state: ResMut<State<GameState>>,
+    mut level_state: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    mut scorecard: ResMut<Scorecard>,
+) {
+    for generated in generated.iter() {
+        if let (Requester::Daily(date), Some(level)) = (generated.requester, &generated.level) {
+            daily.made = Some((date, level.clone()));
+            daily.playing = Some(date);
+            levels.custom = Some(level.clone());
+            let index = current.0;
+            start_game(index, &mut game_state, &mut level_state, &mut current, &mut scorecard);
+        }
+    }
+}
+
+/// Puts the button back if today's level couldn't be made.
+fn reset_daily_button(
+    mut generated: EventReader<Generated>,
+    buttons: Query<&Children, With<DailyButton>>,
+    images: Query<&Children, With<UiImage>>,
+    mut texts: Query<&mut Text>,
+    progress: Res<Progress>,
+) {
+    for generated in generated.iter() {
+        if let (Requester::Daily(_), None) = (generated.requester, &generated.level) {
+            for children in buttons.iter() {
+                set_button_label(children, &images, &mut texts, &daily_label(&progress));
+            }
+        }
+    }
+}
+
+fn record_daily(
+    daily: Res<Daily>,
+    scorecard: Res<Scorecard>,
+    mut progress: ResMut<Progress>,
+) {
+    if let (Some(date), Some(score)) = (daily.playing, scorecard.last()) {
+        progress.record_daily(&date.to_string(), score.strokes, score.time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        for (days, date) in [
+            (0, "1970-01-01"),
+            (11016, "2000-02-29"),
+            (19126, "2022-05-14"),
+            (20744, "2026-10-18"),
+        ] {
+            assert_eq!(Date { days }.to_string(), date);
+        }
+    }
+
+    #[test]
+    fn seed_and_difficulty() {
+        // A Thursday, a Tuesday, a Saturday and a Sunday.
+        for (days, difficulty) in [(0, 3.0 / 6.0), (11016, 1.0 / 6.0), (19126, 5.0 / 6.0), (20744, 1.0)] {
+            let date = Date { days };
+            assert_eq!(date.seed(), days);
+            assert_eq!(date.difficulty(), difficulty);
+        }
+        // Monday 2022-05-16.
+        assert_eq!(Date { days: 19128 }.difficulty(), 0.0);
+    }
+}
@@ -0,0 +1,747 @@
+use std::path::PathBuf;
+use bevy::asset::AssetPath;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use dolly::prelude::*;
+use jamhacks_physics::Motion;
+use crate::level_select::LevelSelection;
+use crate::levels::{CurrentLevel, Level, LevelPack, Levels, LoadResources, PointOfInterest, SavedPlanet};
+use crate::score::{text_style, Scorecard};
+use crate::setup::*;
+use crate::start_menu::start_game;
+
+/// Colours C cycles a planet through.
+const PALETTE: [[f32; 3]; 6] = [
+    [1.0, 1.0, 1.0],
+    [0.9, 0.6, 0.4],
+    [0.5, 0.7, 1.0],
+    [0.6, 0.9, 0.5],
+    [1.0, 0.5, 0.5],
+    [0.8, 0.6, 1.0],
+];
+
+/// How much the arrow keys change mass and radius by.
+const STEP_FACTOR: f32 = 1.1;
+
+/// Builds levels by clicking around rather than editing files. The editor
+/// button starts on a blank level, and E on the main menu opens the level
+/// picked in the level select instead. Keeps the level between visits, and
+/// can try it out or write it to `assets/levels`, adding new levels to the
+/// end of the main pack.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Editor>()
+            .add_system_set(SystemSet::on_update(GameState::Splash).with_system(handle_editor_button))
+            .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(stop_testing))
+            .add_system_set(SystemSet::on_enter(GameState::Editor).with_system(enter_editor))
+            .add_system_set(SystemSet::on_update(GameState::Editor)
+                .with_system(editor_camera)
+                .with_system(crate::zoom)
+                .with_system(click_planets)
+                .with_system(edit_planets)
+                .with_system(name_level)
+                .with_system(leave_editor)
+                .with_system(save_editor_level)
+                .with_system(sync_editor_scene)
+                .with_system(update_editor_hud)
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Editor).with_system(crate::despawn::<EditorElement>))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(add_testing_hint))
+            .add_system_set(SystemSet::on_update(GameState::Game).with_system(back_to_editor));
+    }
+}
+
+/// On the main menu, opens the editor.
+#[derive(Component, Copy, Clone)]
+pub struct EditorButton;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Tool {
+    Move,
+    Start,
+    Goal,
+}
+
+pub struct Editor {
+    level: Level,
+    /// Where the level was loaded from or last saved to, relative to the
+    /// assets folder. Saving writes back there.
+    file: Option<PathBuf>,
+    /// Control S has already been pressed once to say writing over `file` is
+    /// fine.
+    overwrite: bool,
+    /// Picked from the level select, opened on entering the editor.
+    open: Option<usize>,
+    tool: Tool,
+    selected: Option<usize>,
+    /// Where on the selected planet it was grabbed, from its centre.
+    dragging: Option<Vec3>,
+    naming: bool,
+    /// Playing the level from the editor, tab comes back.
+    testing: bool,
+    message: String,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            level: blank_level(),
+            file: None,
+            overwrite: false,
+            open: None,
+            tool: Tool::Move,
+            selected: None,
+            dragging: None,
+            naming: false,
+            testing: false,
+            message: String::new(),
+        }
+    }
+}
+
+fn blank_level() -> Level {
+    Level {
+        name: "Untitled".to_string(),
+        planets: vec![SavedPlanet::new(Vec3::ZERO, 800.0, 1.3)],
+        start: PointOfInterest {
+            planet: 0,
+            direction: Vec3::Y,
+        },
+        goal: PointOfInterest {
+            planet: 0,
+            direction: -Vec3::Y,
+        },
+        par: 3,
+        n_body: None,
+    }
+}
+
+/// Everything the editor draws, rebuilt from `Editor::level` when it changes.
+#[derive(Component)]
+struct EditorElement;
+
+#[derive(Component)]
+struct EditorScene;
+
+#[derive(Component)]
+struct EditorHud;
+
+#[derive(Component)]
+struct TestingHint;
+
+/// The button opens the editor on whatever it had last, E on the level picked
+/// in the level select.
+fn handle_editor_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<EditorButton>)>,
+    keys: Res<Input<KeyCode>>,
+    selection: Res<LevelSelection>,
+    mut editor: ResMut<Editor>,
+    mut game: ResMut<State<GameState>>,
+) {
+    let clicked = buttons.iter().any(|interaction| *interaction == Interaction::Clicked);
+    let edit = keys.just_pressed(KeyCode::E);
+    if !(clicked || edit) || game.set(GameState::Editor).is_err() {
+        return;
+    }
+    if edit {
+        editor.open = Some(selection.0);
+    }
+}
+
+fn stop_testing(mut editor: ResMut<Editor>) {
+    if editor.testing {
+        editor.testing = false;
+    }
+}
+
+fn enter_editor(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    assets: Res<AssetServer>,
+) {
+    if let Some(index) = editor.open.take() {
+        if let Some(level) = levels.get(index, &packs, &level_assets) {
+            editor.level = level.clone();
+            editor.file = levels.path(index, &packs, &assets);
+            editor.overwrite = false;
+            editor.selected = None;
+            editor.message = format!("Editing {}", level.name);
+        }
+    }
+    // Also marks the level changed, so the scene gets built.
+    editor.testing = false;
+    editor.dragging = None;
+
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(10.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section("", text_style(&assets, 22.0), default()),
+        ..default()
+    })
+        .insert(EditorHud)
+        .insert(EditorElement);
+}
+
+/// Right drag turns the camera around the middle of the level.
+fn editor_camera(
+    buttons: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    editor: Res<Editor>,
+    time: Res<Time>,
+    mut camera: Query<(&mut Transform, &mut CameraTag)>,
+) {
+    let (mut transform, mut camera) = match camera.get_single_mut() {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+
+    for event in motion.iter() {
+        if buttons.pressed(MouseButton::Right) {
+            let delta = event.delta * 0.005;
+            let rotation = camera.rig.driver_mut::<Rotation>().rotation;
+            camera.rig.driver_mut::<Rotation>().rotation = Quat::from_rotation_x(-delta.y) * Quat::from_rotation_y(-delta.x) * rotation;
+        }
+    }
+
+    let (focus, _) = editor.level.world().bounds();
+    camera.rig.driver_mut::<Position>().position = focus;
+    camera.rig.driver_mut::<LookAt>().target = focus;
+
+    let new_transform = camera.rig.update(time.delta_seconds());
+    transform.translation = new_transform.position;
+    transform.rotation = new_transform.rotation;
+}
+
+/// Origin and direction of the ray through the cursor.
+fn cursor_ray(windows: &Windows, camera: &Transform, projection: &PerspectiveProjection) -> Option<(Vec3, Vec3)> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let ndc = cursor / Vec2::new(window.width(), window.height()) * 2.0 - Vec2::ONE;
+    let half = (projection.fov / 2.0).tan();
+    let dir = camera.rotation * Vec3::new(ndc.x * half * projection.aspect_ratio, ndc.y * half, -1.0);
+    Some((camera.translation, dir.normalize()))
+}
+
+/// Where the ray hits the plane through `point` facing `normal`.
+fn hit_plane(origin: Vec3, dir: Vec3, point: Vec3, normal: Vec3) -> Option<Vec3> {
+    let denom = dir.dot(normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = (point - origin).dot(normal) / denom;
+    (t > 0.0).then(|| origin + dir * t)
+}
+
+/// The nearest planet the ray hits, and where on its surface.
+fn hit_planet(origin: Vec3, dir: Vec3, level: &Level) -> Option<(usize, Vec3)> {
+    level.world().planets
+        .iter()
+        .enumerate()
+        .filter_map(|(i, planet)| {
+            let offset = origin - planet.pos;
+            let b = offset.dot(dir);
+            let disc = b * b - (offset.length_squared() - planet.radius * planet.radius);
+            if disc < 0.0 {
+                return None;
+            }
+            let t = -b - disc.sqrt();
+            (t > 0.0).then_some((i, t))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, t)| (i, origin + dir * t))
+}
+
+/// Left click selects and drags planets, or puts the start or goal where
+/// the planet was clicked.
+fn click_planets(
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera: Query<(&Transform, &PerspectiveProjection), With<CameraTag>>,
+    mut editor: ResMut<Editor>,
+) {
+    let (camera, projection) = match camera.get_single() {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+    let (origin, dir) = match cursor_ray(&windows, camera, projection) {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    if buttons.just_released(MouseButton::Left) && editor.dragging.is_some() {
+        editor.dragging = None;
+    }
+
+    if buttons.just_pressed(MouseButton::Left) {
+        let hit = hit_planet(origin, dir, &editor.level);
+        let world = editor.level.world();
+        match (editor.tool, hit) {
+            (Tool::Move, Some((index, _))) => {
+                let centre = world.planets[index].pos;
+                editor.selected = Some(index);
+                editor.dragging = match editor.level.planets[index].motion {
+                    Motion::Static | Motion::Free => hit_plane(origin, dir, centre, camera.forward()).map(|grab| grab - centre),
+                    _ => {
+                        editor.message = "Moving planets start where their motion says".to_string();
+                        None
+                    }
+                };
+            }
+            (Tool::Move, None) => editor.selected = None,
+            (Tool::Start, Some((planet, pos))) => {
+                editor.level.start = PointOfInterest {
+                    planet,
+                    direction: pos - world.planets[planet].pos,
+                };
+            }
+            (Tool::Goal, Some((planet, pos))) => {
+                editor.level.goal = PointOfInterest {
+                    planet,
+                    direction: pos - world.planets[planet].pos,
+                };
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    if let (Some(index), Some(grab)) = (editor.selected, editor.dragging) {
+        if !buttons.pressed(MouseButton::Left) {
+            return;
+        }
+        let centre = editor.level.world().planets[index].pos;
+        let target = match hit_plane(origin, dir, centre, camera.forward()) {
+            Some(pos) => pos - grab,
+            None => return,
+        };
+        if editor.level.planets[index].pos != target {
+            editor.level.planets[index].pos = target;
+        }
+    }
+}
+
+/// Keyboard editing of the level and the selected planet.
+fn edit_planets(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    camera: Query<(&Transform, &PerspectiveProjection), With<CameraTag>>,
+    mut editor: ResMut<Editor>,
+) {
+    if editor.naming {
+        return;
+    }
+
+    for (key, tool) in [(KeyCode::Key1, Tool::Move), (KeyCode::Key2, Tool::Start), (KeyCode::Key3, Tool::Goal)] {
+        if keys.just_pressed(key) {
+            editor.tool = tool;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::PageUp) {
+        editor.level.par += 1;
+    } else if keys.just_pressed(KeyCode::PageDown) && editor.level.par > 1 {
+        editor.level.par -= 1;
+    }
+
+    if keys.just_pressed(KeyCode::A) {
+        let (focus, _) = editor.level.world().bounds();
+        let pos = camera
+            .get_single()
+            .ok()
+            .and_then(|(camera, projection)| {
+                let (origin, dir) = cursor_ray(&windows, camera, projection)?;
+                hit_plane(origin, dir, focus, camera.forward())
+            })
+            .unwrap_or(focus);
+        editor.level.planets.push(SavedPlanet::new(pos, 500.0, 1.0));
+        editor.selected = Some(editor.level.planets.len() - 1);
+    }
+
+    let index = match editor.selected {
+        Some(index) if index < editor.level.planets.len() => index,
+        _ => return,
+    };
+
+    if keys.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        if editor.level.planets.len() > 1 {
+            editor.level.remove_planet(index);
+            editor.selected = None;
+        } else {
+            editor.message = "A level needs at least one planet".to_string();
+        }
+        return;
+    }
+
+    let key = [KeyCode::Up, KeyCode::Down, KeyCode::Right, KeyCode::Left, KeyCode::X, KeyCode::C]
+        .into_iter()
+        .find(|key| keys.just_pressed(*key));
+    let key = match key {
+        Some(key) => key,
+        None => return,
+    };
+
+    // Only borrowed mutably once there's an edit to make, as that marks the
+    // editor changed and rebuilds the scene.
+    let planet = &mut editor.level.planets[index];
+    match key {
+        KeyCode::Up => planet.radius *= STEP_FACTOR,
+        KeyCode::Down => planet.radius /= STEP_FACTOR,
+        KeyCode::Right => planet.mass *= STEP_FACTOR,
+        KeyCode::Left => planet.mass /= STEP_FACTOR,
+        KeyCode::X => planet.mass = -planet.mass,
+        _ => {
+            let next = PALETTE
+                .iter()
+                .position(|colour| Vec3::from(*colour) == planet.colour)
+                .map_or(0, |i| (i + 1) % PALETTE.len());
+            planet.colour = Vec3::from(PALETTE[next]);
+        }
+    }
+}
+
+/// F2 starts typing a new name for the level, enter finishes.
+fn name_level(
+    keys: Res<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut editor: ResMut<Editor>,
+) {
+    if !editor.naming {
+        chars.iter().for_each(drop);
+        if keys.just_pressed(KeyCode::F2) {
+            editor.naming = true;
+        }
+        return;
+    }
+
+    for event in chars.iter() {
+        if !event.char.is_control() {
+            editor.level.name.push(event.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        editor.level.name.pop();
+    }
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::F2]) {
+        editor.naming = false;
+    }
+}
+
+/// P tries the level out, control N starts a new one and escape goes back to
+/// the main menu.
+fn leave_editor(
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut levels: ResMut<Levels>,
+    mut level_assets: ResMut<Assets<Level>>,
+    mut game_state: ResMut<State<GameState>>,
+    mut level_state: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    mut scorecard: ResMut<Scorecard>,
+) {
+    if editor.naming {
+        return;
+    }
+    let control = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+
+    if keys.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Splash).unwrap();
+    } else if control && keys.just_pressed(KeyCode::N) {
+        editor.level = blank_level();
+        editor.file = None;
+        editor.overwrite = false;
+        editor.selected = None;
+        editor.message = "New level".to_string();
+    } else if keys.just_pressed(KeyCode::P) {
+        if let Err(err) = editor.level.validate() {
+            editor.message = format!("Can't play this yet: {}", err);
+            return;
+        }
+        levels.custom = Some(level_assets.add(editor.level.clone()));
+        editor.testing = true;
+        let index = current.0;
+        start_game(index, &mut game_state, &mut level_state, &mut current, &mut scorecard);
+    }
+}
+
+/// Control S saves the level. Writing over a level already in the pack needs
+/// it twice. The pack and level being played are updated to match, so there's
+/// no need to restart to play what was saved.
+fn save_editor_level(
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    assets: Res<AssetServer>,
+    levels: Res<Levels>,
+    mut packs: ResMut<Assets<LevelPack>>,
+    mut level_assets: ResMut<Assets<Level>>,
+) {
+    let control = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if editor.naming || !(control && keys.just_pressed(KeyCode::S)) {
+        return;
+    }
+    if let Err(err) = editor.level.validate() {
+        editor.message = format!("Couldn't save: {}", err);
+        return;
+    }
+
+    let existing = editor.file
+        .as_ref()
+        .map(|file| assets.get_handle::<Level, _>(AssetPath::from(file.as_path())));
+    if let (Some(handle), false) = (&existing, editor.overwrite) {
+        editor.overwrite = true;
+        let file = editor.file.as_ref().map_or_else(String::new, |file| file.display().to_string());
+        // Saved bests are kept under the name.
+        editor.message = match level_assets.get(handle).filter(|old| old.name != editor.level.name) {
+            Some(old) => format!(
+                "This writes over {} and drops the best scores on {}, control S again to save",
+                file, old.name
+            ),
+            None => format!("This writes over {}, control S again to save", file),
+        };
+        return;
+    }
+
+    let Editor { level, file, .. } = &mut *editor;
+    let place = match save_level(level, file) {
+        Ok(place) => place,
+        Err(err) => {
+            editor.message = format!("Couldn't save: {}", err);
+            return;
+        }
+    };
+    match (existing, &editor.file) {
+        (Some(handle), _) => {
+            if let Some(level) = level_assets.get_mut(&handle) {
+                *level = editor.level.clone();
+            }
+        }
+        (None, Some(file)) => {
+            if let Some(pack) = packs.get_mut(&levels.pack) {
+                pack.levels.push(assets.load(AssetPath::from(file.as_path())));
+            }
+            // It's this level's own file now.
+            editor.overwrite = true;
+        }
+        (None, None) => {}
+    }
+    editor.message = format!("Saved to {}", place);
+}
+
+/// File name for a level called `name`, e.g. "Under and Over" becomes
+/// "under-and-over.level.ron".
+fn file_name(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "untitled".to_string() } else { slug };
+    format!("{}.level.ron", slug)
+}
+
+/// Writes the level back to `file`, or if it doesn't have one yet, to a new
+/// file next to the others that goes on the end of the pack. Won't write over
+/// a level it didn't load. Returns where it went.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_level(level: &Level, file: &mut Option<PathBuf>) -> Result<String, anyhow::Error> {
+    use std::io::{ErrorKind, Write};
+    use crate::levels::PACK;
+
+    let text = ron::ser::to_string_pretty(level, default())? + "\n";
+    let assets = bevy::asset::FileAssetIo::get_root_path().join("assets");
+
+    let path = match file {
+        Some(file) => {
+            let path = assets.join(file);
+            std::fs::write(&path, text)?;
+            path
+        }
+        None => {
+            let name = file_name(&level.name);
+            let new = std::path::Path::new(PACK).with_file_name(&name);
+            let path = assets.join(&new);
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map_err(|err| match err.kind() {
+                    ErrorKind::AlreadyExists => anyhow::anyhow!("{} already exists, rename the level first", name),
+                    _ => err.into(),
+                })?
+                .write_all(text.as_bytes())?;
+            add_to_pack(&assets.join(PACK), name)?;
+            *file = Some(new);
+            path
+        }
+    };
+    Ok(path.display().to_string())
+}
+
+/// Adds level file `name` to the end of the pack at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn add_to_pack(path: &std::path::Path, name: String) -> Result<(), anyhow::Error> {
+    let mut pack = ron::de::from_str::<crate::levels::SavedLevelPack>(&std::fs::read_to_string(path)?)?;
+    pack.levels.push(name);
+    std::fs::write(path, ron::ser::to_string_pretty(&pack, default())? + "\n")?;
+    Ok(())
+}
+
+/// There's nowhere to write files in the browser, so the level goes to the
+/// console to be copied out.
+#[cfg(target_arch = "wasm32")]
+fn save_level(level: &Level, file: &mut Option<PathBuf>) -> Result<String, anyhow::Error> {
+    let text = ron::ser::to_string_pretty(level, default())?;
+    let name = file.as_ref()
+        .and_then(|file| file.file_name())
+        .map_or_else(|| file_name(&level.name), |name| name.to_string_lossy().into_owned());
+    info!("{}:\n{}", name, text);
+    Ok("the console".to_string())
+}
+
+fn sync_editor_scene(
+    mut commands: Commands,
+    editor: Res<Editor>,
+    scene: Query<Entity, With<EditorScene>>,
+    resources: Res<LoadResources>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    for entity in scene.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let world = editor.level.world();
+    for (i, (saved, planet)) in editor.level.planets.iter().zip(&world.planets).enumerate() {
+        let selected = editor.selected == Some(i);
+        commands.spawn_bundle(PbrBundle {
+            mesh: resources.planet_mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: Color::from(saved.colour.extend(1.0)),
+                base_color_texture: Some(resources.planet_texture.clone()),
+                normal_map_texture: Some(resources.planet_nmap.clone()),
+                emissive: if selected { Color::rgb(0.25, 0.25, 0.1) } else { Color::BLACK },
+                perceptual_roughness: 0.5,
+                ..default()
+            }),
+            transform: Transform::from_translation(planet.pos)
+                .with_scale(Vec3::splat(planet.radius)),
+            ..default()
+        })
+            .insert(EditorScene)
+            .insert(EditorElement);
+    }
+
+    for (point, material) in [(&editor.level.start, &resources.player_mat), (&editor.level.goal, &resources.target_mat)] {
+        if point.planet >= world.planets.len() {
+            continue;
+        }
+        commands.spawn_bundle(PbrBundle {
+            mesh: resources.player_mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_translation(point.resolve(&world, BALL_RAD))
+                .with_scale(Vec3::splat(0.1)),
+            ..default()
+        })
+            .insert(EditorScene)
+            .insert(EditorElement);
+    }
+}
+
+fn update_editor_hud(editor: Res<Editor>, mut hud: Query<&mut Text, With<EditorHud>>) {
+    let level = &editor.level;
+    let name = if editor.naming {
+        format!("{}_ (enter to finish)", level.name)
+    } else {
+        level.name.clone()
+    };
+    let tool = match editor.tool {
+        Tool::Move => "Move planets",
+        Tool::Start => "Place the start",
+        Tool::Goal => "Place the goal",
+    };
+    let selected = match editor.selected.and_then(|i| level.planets.get(i).map(|planet| (i, planet))) {
+        Some((i, planet)) => format!(
+            "Planet {}: mass {:.0}, radius {:.2}, colour ({:.1}, {:.1}, {:.1})\n\
+             Up/down radius, left/right mass, X flips mass, C colour, delete removes",
+            i + 1,
+            planet.mass,
+            planet.radius,
+            planet.colour.x,
+            planet.colour.y,
+            planet.colour.z
+        ),
+        None => "Nothing selected".to_string(),
+    };
+    let file = match &editor.file {
+        Some(file) => format!("Saves to {}", file.display()),
+        None => "Saves as a new level at the end of the pack".to_string(),
+    };
+
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = format!(
+            "{}   Par {}   {} planets   {}\n\
+             Tool: {} (1 move, 2 start, 3 goal)\n\
+             {}\n\
+             A adds a planet, F2 renames, page up/down par, right drag turns\n\
+             P to play, ctrl S saves, ctrl N new level, escape for the menu\n\
+             {}",
+            name,
+            level.par,
+            level.planets.len(),
+            file,
+            tool,
+            selected,
+            editor.message
+        );
+    }
+}
+
+fn add_testing_hint(mut commands: Commands, editor: Res<Editor>, assets: Res<AssetServer>) {
+    if !editor.testing {
+        return;
+    }
+
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                bottom: Val::Px(15.0),
+                right: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section("Testing: tab goes back to the editor", text_style(&assets, 24.0), default()),
+        ..default()
+    })
+        .insert(TestingHint)
+        .insert(GameElement);
+}
+
+/// Gives way if the ball reached the target in the same frame, the complete
+/// menu has a way back out.
+fn back_to_editor(
+    keys: Res<Input<KeyCode>>,
+    editor: Res<Editor>,
+    mut game: ResMut<State<GameState>>,
+    mut level: ResMut<State<LevelState>>,
+) {
+    if editor.testing && keys.just_pressed(KeyCode::Tab) && game.set(GameState::Editor).is_ok() {
+        let _ = level.set(LevelState::None);
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use jamhacks_physics::{Fate, IntegratorKind};
+use crate::physics::{world_from, NBodyMode, PhysicsClock, TimeWarp};
+use crate::reset_ball;
+use crate::score::text_style;
+use crate::setup::*;
+
+/// Orbits longer than this, in simulated seconds, are worth fast-forwarding.
+const LONG_ORBIT: f32 = 20.0;
+
+/// Seconds an escaping ball is left flying before it's put back.
+const ESCAPE_RESET: f32 = 3.0;
+
+/// Watches the ball once it's clear of the planets. A ball that's escaping
+/// is put back where it was launched from after a few seconds, or straight
+/// away with B. A ball on a long orbit can be fast-forwarded with F.
+pub struct EscapePlugin;
+
+impl Plugin for EscapePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Outlook>()
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(add_outlook_hud))
+            .add_system_set(SystemSet::on_update(GameState::Game)
+                .with_system(watch_flight)
+                .with_system(update_outlook_hud)
+            )
+            .add_system_set(SystemSet::on_enter(LevelState::Playing).with_system(stop_fast_forward));
+    }
+}
+
+/// What's going to happen to the ball in flight.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+enum Outlook {
+    #[default]
+    Fine,
+    LongOrbit {
+        period: f32,
+        fast_forward: bool,
+    },
+    Escaping {
+        /// Real seconds left before the ball is put back.
+        reset_in: f32,
+    },
+}
+
+#[derive(Component)]
+struct OutlookHud;
+
+fn stop_fast_forward(mut outlook: ResMut<Outlook>, mut warp: ResMut<TimeWarp>) {
+    *outlook = Outlook::Fine;
+    warp.fast_forward = false;
+}
+
+fn watch_flight(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut ball: Query<(Entity, &mut Transform, &Dynamics, &LastRest), (With<MainBall>, Without<Planet>)>,
+    planets: Query<(&Planet, &Transform)>,
+    integrator: Res<IntegratorKind>,
+    n_body: Res<NBodyMode>,
+    clock: Res<PhysicsClock>,
+    mut outlook: ResMut<Outlook>,
+    mut warp: ResMut<TimeWarp>,
+    mut camera_state: ResMut<CameraState>,
+) {
+    let (entity, mut transform, dynamics, rest) = match ball.get_single_mut() {
+        Ok(x) => x,
+        Err(_) => {
+            if *outlook != Outlook::Fine {
+                *outlook = Outlook::Fine;
+                warp.fast_forward = false;
+            }
+            return;
+        }
+    };
+
+    let mut world = world_from(planets.iter(), *integrator, clock.time);
+    world.n_body = n_body.0;
+    let (centre, radius) = world.bounds();
+
+    // Among the planets the one-body picture is too rough to go on.
+    let fate = if (transform.translation - centre).length() > radius {
+        world.fate(&dynamics.to_ball(transform.translation, 0.0))
+    } else {
+        Fate::Bound { period: 0.0 }
+    };
+
+    *outlook = match (fate, *outlook) {
+        (Fate::Escaping, Outlook::Escaping { reset_in }) => Outlook::Escaping {
+            reset_in: reset_in - time.delta_seconds(),
+        },
+        (Fate::Escaping, _) => Outlook::Escaping {
+            reset_in: ESCAPE_RESET,
+        },
+        (Fate::Bound { period }, Outlook::LongOrbit { fast_forward, .. }) if period > LONG_ORBIT => Outlook::LongOrbit {
+            period,
+            fast_forward,
+        },
+        (Fate::Bound { period }, _) if period > LONG_ORBIT => Outlook::LongOrbit {
+            period,
+            fast_forward: false,
+        },
+        (Fate::Bound { .. }, _) => Outlook::Fine,
+    };
+
+    match &mut *outlook {
+        Outlook::Escaping { reset_in } => {
+            if *reset_in <= 0.0 || keys.just_pressed(KeyCode::B) {
+                reset_ball(&mut commands, entity, &mut transform, rest, &planets, &mut camera_state);
+                *outlook = Outlook::Fine;
+            }
+        }
+        Outlook::LongOrbit { fast_forward, .. } => {
+            if keys.just_pressed(KeyCode::F) {
+                *fast_forward = !*fast_forward;
+            }
+            if keys.just_pressed(KeyCode::B) {
+                reset_ball(&mut commands, entity, &mut transform, rest, &planets, &mut camera_state);
+                *outlook = Outlook::Fine;
+            }
+        }
+        Outlook::Fine => {}
+    }
+
+    warp.fast_forward = matches!(*outlook, Outlook::LongOrbit { fast_forward: true, .. });
+}
+
+fn add_outlook_hud(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(50.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section("", text_style(&assets, 26.0), default()),
+        ..default()
+    })
+        .insert(OutlookHud)
+        .insert(GameElement);
+}
+
+fn update_outlook_hud(
+    outlook: Res<Outlook>,
+    warp: Res<TimeWarp>,
+    mut hud: Query<&mut Text, With<OutlookHud>>,
+) {
+    let message = match *outlook {
+        Outlook::Fine => String::new(),
+        Outlook::LongOrbit { period, fast_forward: false } => format!(
+            "Long orbit of about {:.0}s: F to fast-forward, B to reset",
+            period
+        ),
+        Outlook::LongOrbit { fast_forward: true, .. } => format!(
+            "Fast-forwarding x{}: F to stop, B to reset",
+            warp.factor()
+        ),
+        Outlook::Escaping { reset_in } => format!(
+            "Ball escaping! Resetting in {:.0}s, B to reset now",
+            reset_in.max(0.0).ceil()
+        ),
+    };
+
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
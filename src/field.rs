@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+use bevy::render::view::NoFrustumCulling;
+use jamhacks_physics::IntegratorKind;
+use crate::physics::{world_from, PhysicsClock};
+use crate::setup::*;
+
+/// Arrows on each side of the ball, so the grid is `2 * HALF_GRID + 1` wide.
+const HALF_GRID: i32 = 12;
+
+const SPACING: f32 = 0.5;
+
+/// Field strength that draws an arrow half as long as the spacing. Arrows
+/// only approach the full spacing, so strong fields near planets stay
+/// readable.
+const HALF_LENGTH_ACC: f32 = 2.0;
+
+/// Shows the gravity field on the plane facing the camera through the ball,
+/// toggled with G.
+pub struct FieldOverlayPlugin;
+
+impl Plugin for FieldOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(add_field_overlay))
+            .add_system_set(SystemSet::on_update(GameState::Game)
+                .with_system(toggle_field_overlay)
+                .with_system(update_field_overlay)
+            );
+    }
+}
+
+#[derive(Component)]
+struct FieldOverlay;
+
+fn add_field_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(line_mesh(Vec::new())),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.4, 0.8, 1.0),
+            unlit: true,
+            ..default()
+        }),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    })
+        .insert(FieldOverlay)
+        .insert(NoFrustumCulling)
+        .insert(GameElement);
+}
+
+fn toggle_field_overlay(
+    keys: Res<Input<KeyCode>>,
+    mut overlay: Query<&mut Visibility, With<FieldOverlay>>,
+) {
+    if keys.just_pressed(KeyCode::G) {
+        for mut visibility in overlay.iter_mut() {
+            visibility.is_visible = !visibility.is_visible;
+        }
+    }
+}
+
+fn update_field_overlay(
+    overlay: Query<(&Handle<Mesh>, &Visibility), With<FieldOverlay>>,
+    ball: Query<&Transform, With<MainBall>>,
+    camera: Query<&Transform, With<CameraTag>>,
+    planets: Query<(&Planet, &Transform)>,
+    clock: Res<PhysicsClock>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let (handle, visibility) = match overlay.get_single() {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+    let (ball, camera) = match (ball.get_single(), camera.get_single()) {
+        (Ok(ball), Ok(camera)) => (ball.translation, camera),
+        _ => return,
+    };
+    if !visibility.is_visible {
+        return;
+    }
+
+    // The integrator doesn't matter, only the accelerations are used.
+    let world = world_from(planets.iter(), IntegratorKind::default(), clock.time);
+    let (right, up) = (camera.right(), camera.up());
+    let normal = right.cross(up);
+
+    let mut lines = Vec::new();
+    for i in -HALF_GRID..=HALF_GRID {
+        for j in -HALF_GRID..=HALF_GRID {
+            let pos = ball + (i as f32 * right + j as f32 * up) * SPACING;
+            if world.planets.iter().any(|planet| (pos - planet.pos).length() < planet.radius) {
+                continue;
+            }
+
+            let acc = world.acceleration_at(pos);
+            let acc = acc - normal * acc.dot(normal);
+            let strength = acc.length();
+            if !strength.is_finite() || strength == 0.0 {
+                continue;
+            }
+
+            let dir = acc / strength;
+            let length = SPACING * strength / (strength + HALF_LENGTH_ACC);
+            let tail = pos - dir * length * 0.5;
+            let tip = pos + dir * length * 0.5;
+            let side = normal.cross(dir) * length * 0.2;
+            let back = dir * length * 0.35;
+
+            lines.extend([tail, tip, tip, tip - back + side, tip, tip - back - side]);
+        }
+    }
+
+    if let Some(mesh) = meshes.get_mut(handle) {
+        *mesh = line_mesh(lines);
+    }
+}
+
+/// Line list through `points` in pairs. Never empty, since the renderer
+/// doesn't like empty vertex buffers.
+pub(crate) fn line_mesh(mut points: Vec<Vec3>) -> Mesh {
+    if points.is_empty() {
+        points = vec![Vec3::ZERO, Vec3::ZERO];
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; points.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; points.len()]);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        points.into_iter().map(|point| point.to_array()).collect::<Vec<_>>(),
+    );
+    mesh
+}
@@ -0,0 +1,139 @@
+use std::sync::{Arc, Mutex};
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use jamhacks_physics::Motion;
+use crate::levels::{CurrentLevel, Level, LevelId, LevelPack, Levels, LoadResources};
+use crate::physics::PhysicsClock;
+use crate::progress::Progress;
+use crate::replay::Launched;
+use crate::score::Scorecard;
+use crate::setup::*;
+
+/// How long a ghost shot is followed if it doesn't come to rest.
+const GHOST_SECONDS: f32 = 60.0;
+
+/// Each time the ball is launched, a see-through ghost flies the same stroke
+/// of the best solution to the level alongside it. Only on levels where the
+/// planets stay put, elsewhere they wouldn't be where they were when the
+/// solution was shot and the ghost would fly through them.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GhostFlights>()
+            .add_system_set(SystemSet::on_enter(LevelState::Playing).with_system(plan_ghosts))
+            .add_system_set(SystemSet::on_update(GameState::Game)
+                .with_system(launch_ghost)
+                .with_system(fly_ghost)
+            );
+    }
+}
+
+/// Not a `MainBall`, so it never reaches the target or feels the physics.
+#[derive(Component)]
+struct Ghost {
+    path: Arc<Vec<Vec3>>,
+    /// Physics steps taken on the level when the live ball was launched.
+    launched: u64,
+}
+
+/// Where the ball goes on each stroke of the best solution to the level being
+/// played, one position per physics step. Worked out on the async compute
+/// pool when the level starts, and empty until then.
+#[derive(Default)]
+struct GhostFlights(Arc<Mutex<Vec<Arc<Vec<Vec3>>>>>);
+
+fn plan_ghosts(
+    mut flights: ResMut<GhostFlights>,
+    current: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    progress: Res<Progress>,
+    pool: Res<AsyncComputeTaskPool>,
+) {
+    // A new slot, so a flight still being worked out for the last level
+    // can't land in this one.
+    flights.0 = default();
+
+    // Solutions are only kept for levels in the pack.
+    if levels.custom.is_some() {
+        return;
+    }
+    let level = match levels.current(&current, &packs, &level_assets) {
+        Some(level) => level,
+        None => return,
+    };
+    if !level.planets.iter().all(|planet| planet.motion == Motion::Static) {
+        return;
+    }
+    let shots = match progress.solution(&level.name) {
+        Some(shots) => shots.to_vec(),
+        None => return,
+    };
+
+    let level = level.clone();
+    let slot = Arc::clone(&flights.0);
+    pool.spawn(async move {
+        let paths = shots
+            .iter()
+            .map(|shot| {
+                let frames = shot.replay(&level, (GHOST_SECONDS / shot.settings.step()) as usize);
+                Arc::new(frames.into_iter().map(|frame| frame.ball).collect())
+            })
+            .collect();
+        *slot.lock().unwrap() = paths;
+    }).detach();
+}
+
+fn launch_ghost(
+    mut commands: Commands,
+    mut launched: EventReader<Launched>,
+    ghosts: Query<Entity, With<Ghost>>,
+    current: Res<CurrentLevel>,
+    flights: Res<GhostFlights>,
+    scorecard: Res<Scorecard>,
+    resources: Res<LoadResources>,
+) {
+    let launch = match launched.iter().last() {
+        Some(launch) => launch,
+        None => return,
+    };
+
+    for ghost in ghosts.iter() {
+        commands.entity(ghost).despawn();
+    }
+
+    // The stroke just taken is already counted.
+    let stroke = scorecard.strokes.saturating_sub(1) as usize;
+    let path = match flights.0.lock().unwrap().get(stroke) {
+        Some(path) => Arc::clone(path),
+        None => return,
+    };
+
+    commands.spawn_bundle(PbrBundle {
+        mesh: resources.player_mesh.clone(),
+        material: resources.ghost_mat.clone(),
+        transform: Transform::from_translation(path[0])
+            .with_scale(Vec3::splat(BALL_RAD)),
+        ..default()
+    })
+        .insert(Ghost {
+            path,
+            launched: launch.steps,
+        })
+        .insert(GameElement)
+        .insert(LevelId(current.0));
+}
+
+/// Keeps the ghost in step with the physics, so time warp and pausing affect
+/// it just like the live ball.
+fn fly_ghost(clock: Res<PhysicsClock>, mut ghosts: Query<(&Ghost, &mut Transform)>) {
+    for (ghost, mut transform) in ghosts.iter_mut() {
+        let index = clock.steps.saturating_sub(ghost.launched) as usize;
+        if let Some(pos) = ghost.path.get(index.min(ghost.path.len() - 1)) {
+            transform.translation = *pos;
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use crate::levels::{CurrentLevel, Level, LevelPack, Levels};
+use crate::progress::Progress;
+use crate::score::{format_time, text_style, Scorecard};
+use crate::setup::*;
+use crate::start_menu::start_game;
+
+/// Levels per row of the grid, which up and down move by.
+const COLUMNS: usize = 5;
+
+/// A grid of every level in the pack on the main menu. Arrow keys or the
+/// mouse pick a level, enter or a click plays it and E opens it in the
+/// editor. Holding shift plays locked levels too, for testing.
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LevelSelection>()
+            .add_system_set(SystemSet::on_update(GameState::Splash)
+                .with_system(fill_level_grid)
+                .with_system(select_level)
+                .with_system(colour_level_buttons)
+            );
+    }
+}
+
+/// The node the level buttons go in.
+#[derive(Component)]
+pub struct LevelGrid;
+
+#[derive(Component)]
+struct LevelButton(usize);
+
+#[derive(Default)]
+pub(crate) struct LevelSelection(pub(crate) usize);
+
+/// Each level with whether it's unlocked.
+fn level_list<'a>(
+    levels: &Levels,
+    packs: &Assets<LevelPack>,
+    level_assets: &'a Assets<Level>,
+    progress: &Progress,
+) -> Vec<(&'a Level, bool)> {
+    let mut previous: Option<&Level> = None;
+    (0..levels.len(packs))
+        .filter_map(|i| levels.get(i, packs, level_assets))
+        .map(|level| {
+            let unlocked = progress.is_unlocked(&level.name, previous.map(|level| level.name.as_str()));
+            previous = Some(level);
+            (level, unlocked)
+        })
+        .collect()
+}
+
+fn fill_level_grid(
+    mut commands: Commands,
+    grid: Query<(Entity, Option<&Children>), With<LevelGrid>>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    progress: Res<Progress>,
+    asset_server: Res<AssetServer>,
+) {
+    let (grid, children) = match grid.get_single() {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+    if children.is_some_and(|children| !children.is_empty()) || !levels.is_loaded(&asset_server, &packs) {
+        return;
+    }
+
+    let list = level_list(&levels, &packs, &level_assets, &progress);
+    commands.entity(grid).with_children(|parent| {
+        for (i, (level, unlocked)) in list.into_iter().enumerate() {
+            let best = match progress.best(&level.name) {
+                Some(best) => format!("Best {} in {}", best.strokes, format_time(best.time)),
+                None => "Not finished".to_string(),
+            };
+            let status = if unlocked { best } else { "Locked".to_string() };
+
+            parent.spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(220.0), Val::Px(100.0)),
+                    margin: Rect::all(Val::Px(8.0)),
+                    padding: Rect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            })
+                .insert(LevelButton(i))
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                            format!("{}. {}\nPar {}\n{}", i + 1, level.name, level.par, status),
+                            text_style(&asset_server, 20.0),
+                            default(),
+                        ),
+                        focus_policy: bevy::ui::FocusPolicy::Pass,
+                        ..default()
+                    });
+                });
+        }
+    });
+}
+
+fn select_level(
+    keys: Res<Input<KeyCode>>,
+    buttons: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    mut selection: ResMut<LevelSelection>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    progress: Res<Progress>,
+    mut game_state: ResMut<State<GameState>>,
+    mut level_state: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    mut scorecard: ResMut<Scorecard>,
+) {
+    let count = levels.len(&packs);
+    if count == 0 {
+        return;
+    }
+
+    let mut play = false;
+    for (interaction, button) in buttons.iter() {
+        match interaction {
+            Interaction::Hovered => selection.0 = button.0,
+            Interaction::Clicked => {
+                selection.0 = button.0;
+                play = true;
+            }
+            Interaction::None => {}
+        }
+    }
+
+    let index = selection.0 as isize;
+    let moved = if keys.just_pressed(KeyCode::Left) {
+        index - 1
+    } else if keys.just_pressed(KeyCode::Right) {
+        index + 1
+    } else if keys.just_pressed(KeyCode::Up) {
+        index - COLUMNS as isize
+    } else if keys.just_pressed(KeyCode::Down) {
+        index + COLUMNS as isize
+    } else {
+        index
+    };
+    selection.0 = moved.clamp(0, count as isize - 1) as usize;
+    play |= keys.just_pressed(KeyCode::Return);
+
+    if !play {
+        return;
+    }
+
+    let list = level_list(&levels, &packs, &level_assets, &progress);
+    let unlocked = list.get(selection.0).is_some_and(|(_, unlocked)| *unlocked);
+    let testing = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if unlocked || testing {
+        start_game(selection.0, &mut game_state, &mut level_state, &mut current, &mut scorecard);
+    }
+}
+
+fn colour_level_buttons(
+    selection: Res<LevelSelection>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    progress: Res<Progress>,
+    mut buttons: Query<(&LevelButton, &mut UiColor)>,
+) {
+    let list = level_list(&levels, &packs, &level_assets, &progress);
+    for (button, mut colour) in buttons.iter_mut() {
+        let unlocked = list.get(button.0).is_some_and(|(_, unlocked)| *unlocked);
+        colour.0 = match (button.0 == selection.0, unlocked) {
+            (true, true) => Color::rgb(0.25, 0.45, 0.8),
+            (false, true) => Color::rgb(0.1, 0.2, 0.4),
+            (true, false) => Color::rgb(0.4, 0.4, 0.4),
+            (false, false) => Color::rgb(0.2, 0.2, 0.2),
+        };
+    }
+}
@@ -1,120 +1,466 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadState, LoadedAsset};
 use bevy::prelude::*;
-use crate::{Handle, Mesh, Res, StandardMaterial, Texture, Vec3};
+use bevy::reflect::TypeUuid;
+use jamhacks_physics::{Motion, NBody, World};
+use jamhacks_physics::solve::{solve, Report, Spot, Sweep};
+use serde::{Deserialize, Serialize};
+use crate::{Handle, Mesh, Res, StandardMaterial, Vec3};
 use crate::setup::*;
+use crate::physics::{NBodyMode, PhysicsSettings};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-struct SavedPlanet {
-    pos: Vec3,
-    mass: f32,
-    radius: f32,
-    colour: Vec3,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedPlanet {
+    /// Ignored for planets that move, they start wherever their motion says.
+    #[serde(default)]
+    pub pos: Vec3,
+    pub mass: f32,
+    pub radius: f32,
+    #[serde(default = "white")]
+    pub colour: Vec3,
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    #[serde(default = "default_friction")]
+    pub friction: f32,
+    #[serde(default)]
+    pub motion: Motion,
+    /// Starting velocity, only used by `Free` planets.
+    #[serde(default)]
+    pub vel: Vec3,
 }
 
-macro_rules! make_planet {
-    ($pos:expr, $mass:expr, $rad:expr, $colour:expr) => {
-        SavedPlanet {
-            pos: $pos,
-            mass: $mass,
-            radius: $rad,
-            colour: *$colour
-
-        }
-    };
-    ($pos:expr, $mass:expr, $rad:expr) => {
-        SavedPlanet {
-            pos: $pos,
-            mass: $mass,
-            radius: $rad,
-            colour: *WHITE
+impl SavedPlanet {
+    pub fn new(pos: Vec3, mass: f32, radius: f32) -> Self {
+        Self {
+            pos,
+            mass,
+            radius,
+            colour: white(),
+            restitution: default_restitution(),
+            friction: default_friction(),
+            motion: Motion::Static,
+            vel: Vec3::ZERO,
         }
     }
 }
 
-struct PointOfInterest {
-    planet: usize,
-    direction: Vec3,
+fn white() -> Vec3 {
+    Vec3::ONE
+}
+
+fn default_restitution() -> f32 {
+    jamhacks_physics::DEFAULT_RESTITUTION
+}
+
+fn default_friction() -> f32 {
+    jamhacks_physics::DEFAULT_FRICTION
+}
+
+fn default_par() -> u32 {
+    3
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointOfInterest {
+    pub planet: usize,
+    pub direction: Vec3,
 }
 
 impl PointOfInterest {
-    fn resolve(&self, level: &Level, len: f32) -> Vec3 {
-        level.planets[self.planet].pos + self.direction.normalize() * (level.planets[self.planet].radius + len)
+    pub fn resolve(&self, world: &World, len: f32) -> Vec3 {
+        world.planets[self.planet].pos + self.direction.normalize() * (world.planets[self.planet].radius + len)
     }
 }
 
 
 struct CameraView(Vec3, Vec3);
 
-struct Level {
-    planets: Vec<SavedPlanet>,
-    start: PointOfInterest,
-    goal: PointOfInterest,
-}
-
-lazy_static::lazy_static! {
-    static ref RED: Vec3 = Vec3::new(1.0, 0.0, 0.0);
-    static ref GREEN: Vec3 = Vec3::new(0.0, 1.0, 0.0);
-    static ref BLUE: Vec3 = Vec3::new(0.0, 0.0, 1.0);
-    static ref WHITE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
-    static ref BLACK: Vec3 = Vec3::new(0.0, 0.0, 0.0);
-    static ref PURPLE: Vec3 = Vec3::new(0.6, 0.0, 0.6);
-
-    static ref LEVELS: Vec<Level> = vec![
-        Level { //Level 1
-            planets: vec![
-                make_planet!(Vec3::new(0.0, 0.0, 0.0), 800.0, 1.0),
-            ],
-            start: PointOfInterest {
-                planet: 0,
-                direction: Vec3::new(1.0, 1.0, 1.0),
-            },
-            goal: PointOfInterest {
-                planet: 0,
-                direction: -Vec3::new(1.0, 1.0, 1.0),
-            },
-            // cameras: vec![
-            //     CameraView(Vec3::new(5.0, 5.0, 5.0))
-            // ]
-        },
-        Level { //Level 2
-            planets: vec![
-                make_planet!(Vec3::new(0.0, 0.0, 0.0), 800.0, 1.3, PURPLE),
-                make_planet!(Vec3::new(3.5, 1.0, 1.5), 750.0, 0.9, PURPLE),
-            ],
-            start: PointOfInterest {
-                planet: 0,
-                direction: Vec3::new(0.0, 1.0, 0.0),
-            },
-            goal: PointOfInterest {
-                planet: 1,
-                direction: -Vec3::new(0.0, 1.0, 0.),
-            },
-            // cameras: vec![
-            //     CameraView(Vec3::new(5.0, 5.0, 5.0))
-            // ]
-        },
-        Level { //Level 3
-            planets: vec![
-                make_planet!(Vec3::new(0.0, 0.0, 0.0), 80.0, 1.2, RED),
-                make_planet!(Vec3::new(4.0, 1.0, 2.3), 1000.0, 1.6, RED),
-                make_planet!(Vec3::new(3.0, 0.3, 5.7), 500.0, 0.75, RED),
-                make_planet!(Vec3::new(0.4, 1.4, 3.0), -200.0, 0.3, GREEN), //anti-grav
-            ],
-            start: PointOfInterest {
-                planet: 0,
-                direction: Vec3::new(0.0, 1.0, 0.0),
-            },
-            goal: PointOfInterest {
-                planet: 2,
-                direction: -Vec3::new(0.5, 1.0, 0.62),
-            },
-            // cameras: vec![
-            //     CameraView(Vec3::new(5.0, 5.0, 5.0))
-            // ]
-        },
-    ];
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "5a3b0f0e-7c55-4d3e-9a47-2f6b1c8e9d10"]
+pub struct Level {
+    /// Shown in the level select, and what saved scores are kept under.
+    pub name: String,
+    pub planets: Vec<SavedPlanet>,
+    pub start: PointOfInterest,
+    pub goal: PointOfInterest,
+    /// Strokes a good player needs to finish the level.
+    #[serde(default = "default_par")]
+    pub par: u32,
+    /// Makes free planets attract each other.
+    #[serde(default)]
+    pub n_body: Option<NBody>,
+}
+
+impl Level {
+    /// Checks that every `PointOfInterest` refers to a planet that exists and
+    /// points somewhere, and that every planet has a size and a motion made
+    /// of real numbers, so `resolve` and the physics can't index out of bounds
+    /// or produce NaNs.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        for (name, point) in [("start", &self.start), ("goal", &self.goal)] {
+            if point.planet >= self.planets.len() {
+                return Err(LevelError::PlanetOutOfRange {
+                    point: name,
+                    planet: point.planet,
+                    count: self.planets.len(),
+                });
+            }
+            if !point.direction.is_finite() || point.direction.length_squared() == 0.0 {
+                return Err(LevelError::ZeroDirection { point: name });
+            }
+        }
+
+        for (index, planet) in self.planets.iter().enumerate() {
+            let not_finite = |what| Err(LevelError::NotFinite { planet: index, what });
+            if !planet.pos.is_finite() {
+                return not_finite("position");
+            }
+            if !planet.vel.is_finite() {
+                return not_finite("velocity");
+            }
+            if !planet.mass.is_finite() {
+                return not_finite("mass");
+            }
+            if !(planet.radius > 0.0 && planet.radius.is_finite()) {
+                return Err(LevelError::BadRadius { planet: index });
+            }
+            match &planet.motion {
+                Motion::Orbit(orbit) => {
+                    if orbit.parent >= index {
+                        return Err(LevelError::BadOrbitParent { planet: index, parent: orbit.parent });
+                    }
+                    if !(orbit.semi_major > 0.0 && orbit.semi_major.is_finite()) {
+                        return Err(LevelError::BadOrbitSize { planet: index });
+                    }
+                    // Zero or NaN vectors normalize to NaN, which fails this
+                    // too.
+                    let plane = orbit.normal.normalize().cross(orbit.periapsis.normalize());
+                    if !(plane.length() > 1e-3) {
+                        return Err(LevelError::BadOrbitPlane { planet: index });
+                    }
+                    if !orbit.phase.is_finite() {
+                        return not_finite("orbit phase");
+                    }
+                    if self.planets[orbit.parent].mass <= 0.0 || !(0.0..1.0).contains(&orbit.eccentricity) {
+                        return Err(LevelError::UnboundOrbit { planet: index });
+                    }
+                }
+                Motion::Path(path) => {
+                    if !path.points.iter().all(|point| point.is_finite()) {
+                        return not_finite("path");
+                    }
+                    if !path.period.is_finite() {
+                        return not_finite("path period");
+                    }
+                }
+                Motion::Static | Motion::Free => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes planet `index` out, moving the start or goal to the first planet
+    /// if they were on it and stopping anything orbiting it. Does nothing if
+    /// it's the last planet.
+    pub fn remove_planet(&mut self, index: usize) {
+        if index >= self.planets.len() || self.planets.len() == 1 {
+            return;
+        }
+        self.planets.remove(index);
+
+        for point in [&mut self.start, &mut self.goal] {
+            if point.planet == index {
+                point.planet = 0;
+            } else if point.planet > index {
+                point.planet -= 1;
+            }
+        }
+        for planet in &mut self.planets {
+            if let Motion::Orbit(orbit) = &mut planet.motion {
+                if orbit.parent == index {
+                    planet.motion = Motion::Static;
+                } else if orbit.parent > index {
+                    orbit.parent -= 1;
+                }
+            }
+        }
+    }
+
+    /// The level's planets as the physics sees them at time zero.
+    pub fn world(&self) -> World {
+        let mut world = World {
+            planets: self.planets
+                .iter()
+                .map(|planet| jamhacks_physics::Planet {
+                    vel: planet.vel,
+                    restitution: planet.restitution,
+                    friction: planet.friction,
+                    motion: planet.motion.clone(),
+                    ..jamhacks_physics::Planet::new(planet.pos, planet.mass, planet.radius)
+                })
+                .collect(),
+            n_body: self.n_body,
+            ..default()
+        };
+        world.update_planets();
+        world
+    }
+
+    /// Searches for ways to finish the level, shooting from the start and
+    /// stepping the ball exactly as the game does.
+    pub fn solve(&self, sweep: &Sweep) -> Report {
+        let world = self.world();
+        let spot = |point: &PointOfInterest| Spot {
+            planet: point.planet,
+            offset: point.resolve(&world, BALL_RAD) - world.planets[point.planet].pos,
+        };
+        solve(&world, spot(&self.start), spot(&self.goal), BALL_RAD, sweep)
+    }
+}
+
+/// A thorough search at the game's physics step.
+pub fn game_sweep() -> Sweep {
+    Sweep {
+        dt: PhysicsSettings::default().step(),
+        ..default()
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    PlanetOutOfRange {
+        point: &'static str,
+        planet: usize,
+        count: usize,
+    },
+    ZeroDirection {
+        point: &'static str,
+    },
+    NotFinite {
+        planet: usize,
+        what: &'static str,
+    },
+    BadRadius {
+        planet: usize,
+    },
+    BadOrbitParent {
+        planet: usize,
+        parent: usize,
+    },
+    BadOrbitSize {
+        planet: usize,
+    },
+    BadOrbitPlane {
+        planet: usize,
+    },
+    UnboundOrbit {
+        planet: usize,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::PlanetOutOfRange { point, planet, count } => write!(
+                f,
+                "{} refers to planet {}, but the level only has {} planet(s)",
+                point, planet, count
+            ),
+            LevelError::ZeroDirection { point } => write!(f, "{} has a zero direction", point),
+            LevelError::NotFinite { planet, what } => write!(f, "planet {}'s {} isn't a number", planet, what),
+            LevelError::BadRadius { planet } => write!(f, "planet {} has no size", planet),
+            LevelError::BadOrbitParent { planet, parent } => write!(
+                f,
+                "planet {} orbits planet {}, which doesn't come before it",
+                planet, parent
+            ),
+            LevelError::BadOrbitSize { planet } => write!(
+                f,
+                "planet {} has an orbit with no size",
+                planet
+            ),
+            LevelError::BadOrbitPlane { planet } => write!(
+                f,
+                "planet {} has an orbit with no plane, its normal is zero or its periapsis lies along it",
+                planet
+            ),
+            LevelError::UnboundOrbit { planet } => write!(
+                f,
+                "planet {} has an orbit that isn't closed",
+                planet
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+/// An ordered list of levels, so adding a level only means adding a file and
+/// an entry to the pack.
+#[derive(Debug, TypeUuid)]
+#[uuid = "0f1c9b4e-2d7a-4b8e-8c61-93d5e2a7f4b3"]
+pub struct LevelPack {
+    pub levels: Vec<Handle<Level>>,
 }
 
-fn spawn_level<const LEVEL: usize>(
+/// A pack file: level file names, relative to the pack.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedLevelPack {
+    pub(crate) levels: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<Level>(bytes)?;
+            level.validate()?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct LevelPackLoader;
+
+impl AssetLoader for LevelPackLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let saved = ron::de::from_bytes::<SavedLevelPack>(bytes)?;
+            let dir = load_context.path().parent().unwrap_or_else(|| Path::new(""));
+
+            let paths = saved.levels
+                .iter()
+                .map(|file| AssetPath::new(dir.join(file), None))
+                .collect::<Vec<_>>();
+
+            let levels = paths
+                .iter()
+                .map(|path| load_context.get_handle(path.clone()))
+                .collect();
+
+            load_context.set_default_asset(LoadedAsset::new(LevelPack { levels }).with_dependencies(paths));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pack.ron"]
+    }
+}
+
+/// The pack the game plays through, relative to the assets folder.
+pub const PACK: &str = "levels/main.pack.ron";
+
+/// Handle to the level pack the game plays through.
+pub struct Levels {
+    pub pack: Handle<LevelPack>,
+    /// Played instead of the pack while set, e.g. a level being tried out in
+    /// the editor.
+    pub custom: Option<Handle<Level>>,
+}
+
+impl Levels {
+    /// Whether every level in the pack is ready to play. Levels that failed
+    /// to load are taken out of the pack by `drop_failed_levels`, so they
+    /// don't hold the rest up.
+    pub fn is_loaded(&self, asset_server: &AssetServer, packs: &Assets<LevelPack>) -> bool {
+        match packs.get(&self.pack) {
+            Some(pack) => !pack.levels.is_empty()
+                && asset_server.get_group_load_state(pack.levels.iter().map(|x| x.id)) == LoadState::Loaded,
+            None => false,
+        }
+    }
+
+    pub fn len(&self, packs: &Assets<LevelPack>) -> usize {
+        packs.get(&self.pack).map_or(0, |pack| pack.levels.len())
+    }
+
+    pub fn get<'a>(&self, index: usize, packs: &Assets<LevelPack>, levels: &'a Assets<Level>) -> Option<&'a Level> {
+        packs
+            .get(&self.pack)
+            .and_then(|pack| pack.levels.get(index))
+            .and_then(|handle| levels.get(handle))
+    }
+
+    /// The file level `index` in the pack was loaded from, relative to the
+    /// assets folder.
+    pub fn path(&self, index: usize, packs: &Assets<LevelPack>, asset_server: &AssetServer) -> Option<PathBuf> {
+        packs
+            .get(&self.pack)
+            .and_then(|pack| pack.levels.get(index))
+            .and_then(|handle| asset_server.get_handle_path(handle))
+            .map(|path| path.path().to_path_buf())
+    }
+
+    /// The level being played: the custom one if there is one, otherwise
+    /// `current` from the pack.
+    pub fn current<'a>(&self, current: &CurrentLevel, packs: &Assets<LevelPack>, levels: &'a Assets<Level>) -> Option<&'a Level> {
+        match &self.custom {
+            Some(handle) => levels.get(handle),
+            None => self.get(current.0, packs, levels),
+        }
+    }
+}
+
+pub fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Levels {
+        pack: asset_server.load(PACK),
+        custom: None,
+    });
+}
+
+/// Takes any level that failed to load, e.g. one that doesn't pass
+/// `validate`, out of the pack so the others can still be played. The loader
+/// has already logged why.
+pub fn drop_failed_levels(
+    levels: Res<Levels>,
+    asset_server: Res<AssetServer>,
+    mut packs: ResMut<Assets<LevelPack>>,
+) {
+    let failed = |handle: &Handle<Level>| asset_server.get_load_state(handle) == LoadState::Failed;
+    // Only borrowed mutably when there's something to drop, so the pack
+    // isn't marked changed every frame.
+    if !packs.get(&levels.pack).is_some_and(|pack| pack.levels.iter().any(failed)) {
+        return;
+    }
+    if let Some(pack) = packs.get_mut(&levels.pack) {
+        pack.levels.retain(|handle| {
+            if failed(handle) {
+                let path = asset_server.get_handle_path(handle);
+                warn!("Skipping level {:?}, it didn't load", path.as_ref().map(|path| path.path()));
+                return false;
+            }
+            true
+        });
+    }
+}
+
+/// Back on the main menu, the pack is played again.
+pub fn clear_custom_level(mut levels: ResMut<Levels>) {
+    levels.custom = None;
+}
+
+fn spawn_level(
+    index: usize,
+    level: &Level,
     commands: &mut Commands,
     mesh: &Handle<Mesh>,
     player_mesh: &Handle<Mesh>,
@@ -124,48 +470,75 @@ fn spawn_level<const LEVEL: usize>(
     texture: &Handle<Image>,
     nmap: &Handle<Image>,
 ) {
-    for planet in &LEVELS[LEVEL].planets {
-        crate::add_planet::<LEVEL>(
+    let world = level.world();
+
+    let entities = level.planets
+        .iter()
+        .zip(&world.planets)
+        .enumerate()
+        .map(|(i, (saved, planet))| crate::add_planet(
             commands,
-            planet.pos,
-            planet.mass,
-            planet.radius,
+            index,
+            i,
+            planet,
             &mesh,
             material.add(StandardMaterial {
-                base_color: Color::from(planet.colour.extend(1.0)).into(),
+                base_color: Color::from(saved.colour.extend(1.0)).into(),
                 base_color_texture: Some(texture.clone()),
                 normal_map_texture: Some(nmap.clone()),
                 perceptual_roughness: 0.5,
                 ..default()
             }),
-        );
-    }
+        ))
+        .collect::<Vec<_>>();
 
-    crate::add_ball::<LEVEL>(
+    crate::add_ball(
         commands,
-        LEVELS[LEVEL].start.resolve(&LEVELS[LEVEL], BALL_RAD),
+        index,
+        level.start.resolve(&world, BALL_RAD),
         1.0,
         BALL_RAD,
         &player_mesh,
         &player_material,
         None,
-        LEVELS[LEVEL].planets[LEVELS[LEVEL].start.planet].pos
+        entities[level.start.planet],
+        world.planets[level.start.planet].pos
     );
 
-    crate::add_target::<LEVEL>(
+    crate::add_target(
         commands,
-        LEVELS[LEVEL].goal.resolve(&LEVELS[LEVEL], BALL_RAD),
+        index,
+        level.goal.resolve(&world, BALL_RAD),
         &player_mesh,
-        &target_material
+        &target_material,
+        entities[level.goal.planet],
+        world.planets[level.goal.planet].pos
     );
 }
 
-pub fn load<const LEVEL: usize>(
+pub fn load(
     mut commands: Commands,
+    current: Res<CurrentLevel>,
     resources: Res<LoadResources>,
-    assets: ResMut<Assets<StandardMaterial>>
+    assets: ResMut<Assets<StandardMaterial>>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    mut n_body: ResMut<NBodyMode>,
 ) {
-    spawn_level::<LEVEL>(
+    let level = match levels.current(&current, &packs, &level_assets) {
+        Some(level) => level,
+        None => {
+            error!("Level {} is not loaded.", current.0);
+            return;
+        }
+    };
+
+    n_body.0 = level.n_body;
+
+    spawn_level(
+        current.0,
+        level,
         &mut commands,
         &resources.planet_mesh,
         &resources.player_mesh,
@@ -183,8 +556,180 @@ pub struct LoadResources {
     pub(crate) planet_texture: Handle<Image>,
     pub(crate) planet_nmap: Handle<Image>,
     pub(crate) player_mat: Handle<StandardMaterial>,
-    pub(crate) target_mat: Handle<StandardMaterial>
+    pub(crate) target_mat: Handle<StandardMaterial>,
+    /// See-through, for the ghost of the best solution.
+    pub(crate) ghost_mat: Handle<StandardMaterial>,
 }
 
+/// On everything `spawn_level` makes, with the level's index in the pack, so
+/// it all goes when the level does and the target knows which level it ends.
 #[derive(Component)]
-pub struct LevelId<const X: usize>;
+pub struct LevelId(pub usize);
+
+/// Index into the level pack of the level being played.
+pub struct CurrentLevel(pub usize);
+
+/// Switches to level `index`, reloading if it's the one already being played.
+pub fn go_to_level(index: usize, current: &mut CurrentLevel, state: &mut State<LevelState>) {
+    current.0 = index;
+    if *state.current() == LevelState::Playing {
+        state.restart().unwrap();
+    } else {
+        state.set(LevelState::Playing).unwrap();
+    }
+}
+
+/// Advances to the level after the current one. Returns `false` without
+/// changing anything if the current level is the last of the `count` levels.
+pub fn next_level(current: &mut CurrentLevel, count: usize, state: &mut State<LevelState>) -> bool {
+    if current.0 + 1 < count {
+        go_to_level(current.0 + 1, current, state);
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamhacks_physics::{Orbit, Path as MotionPath};
+
+    fn level() -> Level {
+        let mut moon = SavedPlanet::new(Vec3::ZERO, 50.0, 0.5);
+        moon.motion = Motion::Orbit(Orbit {
+            parent: 0,
+            semi_major: 5.0,
+            eccentricity: 0.1,
+            periapsis: Vec3::X,
+            normal: Vec3::Y,
+            phase: 0.0,
+        });
+        Level {
+            name: "Test".to_string(),
+            planets: vec![SavedPlanet::new(Vec3::ZERO, 800.0, 1.3), moon],
+            start: PointOfInterest {
+                planet: 0,
+                direction: Vec3::Y,
+            },
+            goal: PointOfInterest {
+                planet: 1,
+                direction: Vec3::X,
+            },
+            par: 2,
+            n_body: None,
+        }
+    }
+
+    fn orbit(level: &mut Level) -> &mut Orbit {
+        match &mut level.planets[1].motion {
+            Motion::Orbit(orbit) => orbit,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn good_level() {
+        assert!(level().validate().is_ok());
+    }
+
+    #[test]
+    fn shipped_levels() {
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+        let pack = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(PACK);
+        for file in ron::de::from_str::<SavedLevelPack>(&read(&pack)).unwrap().levels {
+            let level = ron::de::from_str::<Level>(&read(&pack.with_file_name(&file))).unwrap();
+            if let Err(err) = level.validate() {
+                panic!("{} is invalid: {}", file, err);
+            }
+        }
+    }
+
+    #[test]
+    fn bad_points() {
+        let mut bad = level();
+        bad.goal.planet = 2;
+        assert!(matches!(bad.validate(), Err(LevelError::PlanetOutOfRange { point: "goal", .. })));
+
+        for direction in [Vec3::ZERO, Vec3::new(f32::NAN, 1.0, 0.0)] {
+            let mut bad = level();
+            bad.start.direction = direction;
+            assert!(matches!(bad.validate(), Err(LevelError::ZeroDirection { point: "start" })));
+        }
+    }
+
+    #[test]
+    fn bad_planets() {
+        for radius in [0.0, -1.0, f32::NAN] {
+            let mut bad = level();
+            bad.planets[0].radius = radius;
+            assert!(matches!(bad.validate(), Err(LevelError::BadRadius { planet: 0 })), "radius {}", radius);
+        }
+
+        let mut bad = level();
+        bad.planets[0].pos.x = f32::NAN;
+        assert!(matches!(bad.validate(), Err(LevelError::NotFinite { planet: 0, what: "position" })));
+
+        let mut bad = level();
+        bad.planets[1].vel.y = f32::INFINITY;
+        assert!(matches!(bad.validate(), Err(LevelError::NotFinite { planet: 1, what: "velocity" })));
+    }
+
+    #[test]
+    fn bad_orbits() {
+        for semi_major in [0.0, -2.0, f32::NAN] {
+            let mut bad = level();
+            orbit(&mut bad).semi_major = semi_major;
+            assert!(matches!(bad.validate(), Err(LevelError::BadOrbitSize { planet: 1 })), "semi-major {}", semi_major);
+        }
+
+        let mut bad = level();
+        orbit(&mut bad).parent = 1;
+        assert!(matches!(bad.validate(), Err(LevelError::BadOrbitParent { planet: 1, parent: 1 })));
+
+        let mut bad = level();
+        orbit(&mut bad).eccentricity = 1.0;
+        assert!(matches!(bad.validate(), Err(LevelError::UnboundOrbit { planet: 1 })));
+
+        let planes = [
+            (Vec3::ZERO, Vec3::X),
+            (Vec3::new(f32::NAN, 1.0, 0.0), Vec3::X),
+            (Vec3::Y, Vec3::ZERO),
+            (Vec3::Y, Vec3::new(0.0, -2.0, 0.0)),
+            (Vec3::Y, Vec3::new(f32::INFINITY, 0.0, 0.0)),
+        ];
+        for (normal, periapsis) in planes {
+            let mut bad = level();
+            orbit(&mut bad).normal = normal;
+            orbit(&mut bad).periapsis = periapsis;
+            assert!(
+                matches!(bad.validate(), Err(LevelError::BadOrbitPlane { planet: 1 })),
+                "normal {}, periapsis {}", normal, periapsis
+            );
+        }
+
+        let mut bad = level();
+        orbit(&mut bad).phase = f32::NAN;
+        assert!(matches!(bad.validate(), Err(LevelError::NotFinite { planet: 1, what: "orbit phase" })));
+    }
+
+    #[test]
+    fn paths() {
+        let path = |points: Vec<Vec3>, period| {
+            let mut level = level();
+            level.planets[1].motion = Motion::Path(MotionPath { points, period });
+            level.validate()
+        };
+        assert!(path(vec![Vec3::ZERO, Vec3::X * 5.0, Vec3::Z * 5.0], 10.0).is_ok());
+        assert!(matches!(
+            path(vec![Vec3::ZERO, Vec3::new(f32::NAN, 0.0, 0.0)], 10.0),
+            Err(LevelError::NotFinite { planet: 1, what: "path" })
+        ));
+        for period in [f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                path(vec![Vec3::ZERO, Vec3::X * 5.0], period),
+                Err(LevelError::NotFinite { planet: 1, what: "path period" })
+            ));
+        }
+    }
+}
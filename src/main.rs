@@ -3,9 +3,7 @@
 use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
-use bevy::render::render_resource::Texture;
 use dolly::prelude::*;
-use rand::{Rng, thread_rng};
 
 mod setup;
 use setup::*;
@@ -15,67 +13,95 @@ mod start_menu;
 use start_menu::MainMenuPlugin;
 mod levels;
 use levels::*;
+mod physics;
+use physics::*;
+mod field;
+use field::FieldOverlayPlugin;
+mod score;
+use score::{ScorePlugin, Scorecard};
+mod progress;
+use progress::Progress;
+mod save;
+use save::SavePlugin;
+mod level_select;
+use level_select::LevelSelectPlugin;
+mod complete_menu;
+use complete_menu::CompleteMenuPlugin;
+mod pause_menu;
+use pause_menu::PauseMenuPlugin;
+mod escape;
+use escape::EscapePlugin;
+mod replay;
+use replay::{Launched, ReplayPlugin, ShotLog};
+mod ghost;
+use ghost::GhostPlugin;
+mod editor;
+use editor::EditorPlugin;
+mod random_level;
+use random_level::RandomLevelPlugin;
+mod daily;
+use daily::DailyPlugin;
+mod stars;
+use stars::StarfieldPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+mod check;
+use jamhacks_physics::{Ball, Collision, IntegratorKind, NBody, Trajectory, GOAL_REACH, OUT_OF_BOUNDS_MARGIN, OUT_OF_BOUNDS_SCALE};
 
 struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_startup_system(add_resources)
-            .add_system_set(SystemSet::on_enter(GameState::Game)
-                .with_system(add_stars)
+            .add_asset::<Level>()
+            .add_asset::<LevelPack>()
+            .init_asset_loader::<LevelLoader>()
+            .init_asset_loader::<LevelPackLoader>()
+            .init_resource::<PhysicsSettings>()
+            .init_resource::<PhysicsClock>()
+            .init_resource::<IntegratorKind>()
+            .init_resource::<NBodyMode>()
+            .init_resource::<TimeWarp>()
+            .add_stage_before(
+                CoreStage::Update,
+                PhysicsStage,
+                SystemStage::parallel()
+                    .with_run_criteria(physics_tick)
+                    .with_system(move_planets.label(PhysicsSystem::Planets))
+                    .with_system(ride_planets.after(PhysicsSystem::Planets))
+                    .with_system(update_acc
+                        .label(PhysicsSystem::Acceleration)
+                        .after(PhysicsSystem::Planets))
+                    .with_system(update_pos_vel
+                        .label(PhysicsSystem::Integrate)
+                        .after(PhysicsSystem::Acceleration))
+                    .with_system(collide
+                        .label(PhysicsSystem::Collide)
+                        .after(PhysicsSystem::Integrate))
+                    .with_system(touch_target
+                        .label(PhysicsSystem::Target)
+                        .after(PhysicsSystem::Collide))
             )
+            .add_startup_system(add_resources)
+            .add_startup_system(load_levels)
+            .add_system(drop_failed_levels)
+            .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(clear_custom_level))
             .add_system_set(SystemSet::on_update(GameState::Game)
-                .with_system(skip_level)
-                .with_system(update_timestep)
-                .with_system(update_acc)
-                .with_system(update_pos_vel)
-                .with_system(collide)
+                .with_system(restart_or_skip_level)
+                .with_system(reset_lost_ball)
+                .with_system(cycle_integrator)
+                .with_system(change_time_warp)
                 .with_system(camera_movement)
                 .with_system(aim)
-                .with_system(touch_target)
                 .with_system(zoom)
             )
             .add_system_set(SystemSet::on_exit(GameState::Game)
                 .with_system(despawn::<GameElement>)
             )
-            .add_system_set(SystemSet::on_enter(LevelNumber::One).with_system(load::<0>))
-            .add_system_set(SystemSet::on_enter(LevelNumber::Two).with_system(load::<1>))
-            .add_system_set(SystemSet::on_enter(LevelNumber::Three).with_system(load::<2>))
-            .add_system_set(SystemSet::on_exit(LevelNumber::One).with_system(despawn::<LevelId<0>>))
-            .add_system_set(SystemSet::on_exit(LevelNumber::Two).with_system(despawn::<LevelId<1>>))
-            .add_system_set(SystemSet::on_exit(LevelNumber::Three).with_system(despawn::<LevelId<2>>));
-    }
-}
-
-fn add_stars(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    let mut rand = thread_rng();
-
-    let mut rand = || rand.gen_range(-1.0f32..1.0);
-
-    let material = materials.add(StandardMaterial {
-        base_color: Color::rgb(251.0 / 255.0, 1.0, 201.0 / 255.0),
-        emissive: Color::rgb(251.0 / 255.0, 1.0, 201.0 / 255.0),
-        ..default()
-    });
-
-    let mesh = meshes.add(Mesh::from(shape::Icosphere { radius: 4.0, subdivisions: 3 }));
-
-    for _ in 0..4000 {
-        let pos = Vec3::new(rand(), rand(), rand()).normalize() * 5000.0;
-        let scl = rand() + 2.0;
-        commands
-            .spawn_bundle(PbrBundle {
-                mesh: mesh.clone(),
-                material: material.clone(),
-                transform: Transform::from_translation(pos)
-                    .with_scale(Vec3::splat(scl)),
-                ..default()
-            });
+            .add_system_set(SystemSet::on_enter(LevelState::Playing)
+                .with_system(load)
+                .with_system(reset_clock)
+            )
+            .add_system_set(SystemSet::on_exit(LevelState::Playing).with_system(despawn::<LevelId>));
     }
 }
 
@@ -96,7 +122,13 @@ fn add_resources(
                  base_color: Color::rgb(1.0, 1.0, 0.0),
                  emissive: Color::rgb(1.0, 1.0, 1.0),
                  ..default()
-             })
+             }),
+             ghost_mat: materials.add(StandardMaterial {
+                 base_color: Color::rgba(0.8, 0.9, 1.0, 0.35),
+                 emissive: Color::rgb(0.2, 0.25, 0.3),
+                 alpha_mode: AlphaMode::Blend,
+                 ..default()
+             }),
          }
      );
 
@@ -140,10 +172,16 @@ fn add_resources(
     commands.insert_resource(ProjectedResources {
         mesh: meshes.add(Mesh::from(shape::Icosphere { subdivisions: 5, radius: 1.0 })),
         material: materials.add(Color::rgb(3.0 / 255.0, 252.0 / 255.0, 90.0 / 255.0).into()),
-    });
-
-    commands.insert_resource(DeltaTime {
-        time: 1.0 / 60.0,
+        bounce_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.55, 0.1),
+            emissive: Color::rgb(0.6, 0.3, 0.0),
+            ..default()
+        }),
+        rest_material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 1.0, 1.0),
+            emissive: Color::rgb(0.8, 0.8, 0.8),
+            ..default()
+        }),
     });
 
     commands
@@ -153,47 +191,154 @@ fn add_resources(
         });
 }
 
-fn skip_level(
+/// R restarts the level, N skips to the next one.
+fn restart_or_skip_level(
     keys: Res<Input<KeyCode>>,
-    mut level: ResMut<State<LevelNumber>>,
+    mut current: ResMut<CurrentLevel>,
+    mut level: ResMut<State<LevelState>>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
 ) {
     if keys.just_pressed(KeyCode::R) {
-        let next = match level.current() {
-            LevelNumber::One => LevelNumber::Two,
-            LevelNumber::Two => LevelNumber::Three,
-            LevelNumber::Three => LevelNumber::One,
-            LevelNumber::None => panic!("Cannot cheat in this state."),
-        };
-        println!("Going to {:?}", next);
-        level.set(next).unwrap();
+        let index = current.0;
+        go_to_level(index, &mut current, &mut level);
+        info!("Restarting level {}", current.0);
+    } else if keys.just_pressed(KeyCode::N) && levels.custom.is_none() {
+        if !next_level(&mut current, levels.len(&packs), &mut level) {
+            go_to_level(0, &mut current, &mut level);
+        }
+        info!("Going to level {}", current.0);
     }
 }
 
+/// Puts the ball back where it was launched from once it's too far from
+/// every planet to be coming back in any reasonable time.
+fn reset_lost_ball(
+    mut commands: Commands,
+    mut ball: Query<(Entity, &mut Transform, &LastRest), (With<MainBall>, With<Dynamics>, Without<Planet>)>,
+    planets: Query<(&Planet, &Transform)>,
+    integrator: Res<IntegratorKind>,
+    clock: Res<PhysicsClock>,
+    mut camera_state: ResMut<CameraState>,
+) {
+    let world = world_from(planets.iter(), *integrator, clock.time);
+    let (centre, radius) = world.bounds();
+
+    for (entity, mut transform, rest) in ball.iter_mut() {
+        if (transform.translation - centre).length() < radius * OUT_OF_BOUNDS_SCALE + OUT_OF_BOUNDS_MARGIN {
+            continue;
+        }
+
+        reset_ball(&mut commands, entity, &mut transform, rest, &planets, &mut camera_state);
+        info!("Ball out of bounds, resetting");
+    }
+}
+
+/// Puts a ball in flight back where it was last launched from.
+pub(crate) fn reset_ball(
+    commands: &mut Commands,
+    entity: Entity,
+    transform: &mut Transform,
+    rest: &LastRest,
+    planets: &Query<(&Planet, &Transform)>,
+    camera_state: &mut CameraState,
+) {
+    if let Ok((_, planet)) = planets.get(rest.0.planet) {
+        transform.translation = planet.translation + rest.0.offset;
+    }
+    commands.entity(entity)
+        .remove::<Dynamics>()
+        .insert(rest.0);
+    *camera_state = CameraState::Around {
+        planet: rest.0.planet,
+    };
+}
+
+/// Only while the ball is at rest, so a shot is flown by one integrator all
+/// the way and its replay matches.
+fn cycle_integrator(
+    keys: Res<Input<KeyCode>>,
+    mut integrator: ResMut<IntegratorKind>,
+    flying: Query<(), (With<MainBall>, With<Dynamics>)>,
+) {
+    if keys.just_pressed(KeyCode::I) {
+        if !flying.is_empty() {
+            info!("Can't change integrator with the ball in flight");
+            return;
+        }
+        *integrator = integrator.next();
+        info!("Using {:?} integrator", *integrator);
+    }
+}
+
+fn change_time_warp(
+    keys: Res<Input<KeyCode>>,
+    mut warp: ResMut<TimeWarp>,
+) {
+    if keys.just_pressed(KeyCode::RBracket) {
+        warp.faster();
+    } else if keys.just_pressed(KeyCode::LBracket) {
+        warp.slower();
+    }
+}
+
+/// Checked every physics step rather than every frame, so a fast ball or a
+/// high time warp can't carry the ball straight past the target.
 fn touch_target(
-    target: Query<&Transform, With<Target>>,
+    mut commands: Commands,
+    target: Query<(Entity, &Transform, &LevelId), With<Target>>,
     ball: Query<&Transform, With<MainBall>>,
-    mut level: ResMut<State<LevelNumber>>,
     mut game: ResMut<State<GameState>>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    mut scorecard: ResMut<Scorecard>,
+    mut progress: ResMut<Progress>,
+    shots: Res<ShotLog>,
 ) {
     for ball in ball.iter() {
-        for target in target.iter() {
-            if (ball.translation - target.translation).length() < 0.3 {
-                let next = match level.current() {
-                    LevelNumber::One => LevelNumber::Two,
-                    LevelNumber::Two => LevelNumber::Three,
-                    LevelNumber::Three => {
-                        game.set(GameState::Splash).unwrap();
-                        LevelNumber::None
-                    },
-                    LevelNumber::None => panic!("Cannot cheat in this state."),
+        for (entity, target, &LevelId(index)) in target.iter() {
+            if (ball.translation - target.translation).length() < GOAL_REACH {
+                let level = match levels.current(&CurrentLevel(index), &packs, &level_assets) {
+                    Some(level) => level,
+                    None => return,
                 };
-                level.set(next).unwrap();
+
+                // A custom level is played on its own and doesn't count
+                // towards progress.
+                let custom = levels.custom.is_some();
+                let next = if !custom && index + 1 < levels.len(&packs) {
+                    GameState::LevelComplete
+                } else {
+                    GameState::GameComplete
+                };
+                // Something else, e.g. pausing, already changed the state this
+                // frame. The target is tried again once the game carries on.
+                if game.push(next).is_err() {
+                    return;
+                }
+
+                // Only counts once, the level is reloaded after the menu.
+                commands.entity(entity).remove::<Target>();
+                scorecard.finish(index, level.par);
+                if custom {
+                    return;
+                }
+
+                if let Some(score) = scorecard.last() {
+                    progress.record(&level.name, score.strokes, score.time);
+                }
+                progress.record_solution(&level.name, &shots.shots);
+                if let Some(next) = levels.get(index + 1, &packs, &level_assets) {
+                    progress.unlock(&next.name);
+                }
+                return;
             }
         }
     }
 }
 
-fn zoom(
+pub(crate) fn zoom(
     mut wheel_events: EventReader<MouseWheel>,
     mut rig: Query<&mut CameraTag>,
 ) {
@@ -217,14 +362,6 @@ impl Plugin for MenuPlugin {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-enum LevelNumber {
-    None,
-    One,
-    Two,
-    Three,
-}
-
 fn despawn<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in to_despawn.iter() {
         commands.entity(entity).despawn_recursive();
@@ -232,15 +369,38 @@ fn despawn<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Comma
 }
 
 fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let args = std::env::args().collect::<Vec<_>>();
+        if args.get(1).map(String::as_str) == Some("--check-levels") {
+            let solvable = check::check_levels(&args[2..]);
+            std::process::exit(if solvable { 0 } else { 1 });
+        }
+    }
+
     App::new()
         .add_state(GameState::Splash)
-        .add_state(LevelNumber::None)
+        .add_state(LevelState::None)
+        .insert_resource(CurrentLevel(0))
         .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.08)))
         .insert_resource(Msaa { samples: 4 })
         .add_plugins(DefaultPlugins)
         .add_plugin(GameAudioPlugin)
         .add_plugin(MainMenuPlugin)
         .add_plugin(GamePlugin)
+        .add_plugin(FieldOverlayPlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(CompleteMenuPlugin)
+        .add_plugin(PauseMenuPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(LevelSelectPlugin)
+        .add_plugin(EscapePlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GhostPlugin)
+        .add_plugin(EditorPlugin)
+        .add_plugin(RandomLevelPlugin)
+        .add_plugin(DailyPlugin)
+        .add_plugin(StarfieldPlugin)
         // .add_startup_system(setup)
         //game runningfl
         .run();
@@ -249,19 +409,16 @@ fn main() {
 #[derive(Component)]
 struct Target;
 
-#[derive(Component)]
-struct OnGround {
-    center_of_planet: Vec3,
-}
-
-pub fn add_ball<const LEVEL: usize>(
+pub fn add_ball(
     commands: &mut Commands,
+    level: usize,
     pos: Vec3,
     mass: f32,
     radius: f32,
     mesh: &Handle<Mesh>,
     material: &Handle<StandardMaterial>,
     vel: Option<Vec3>,
+    planet: Entity,
     center_of_planet: Vec3,
 ) {
     let entity = commands.spawn_bundle(PbrBundle {
@@ -277,52 +434,57 @@ pub fn add_ball<const LEVEL: usize>(
         })
         .insert(MainBall)
         .insert(GameElement)
-        .insert(LevelId::<LEVEL>)
+        .insert(LevelId(level))
         .insert(OnGround {
-            center_of_planet
+            planet,
+            offset: pos - center_of_planet,
         })
         .id();
     if let Some(vel) = vel {
         commands.entity(entity).insert(Dynamics {
             acc: Vec3::ZERO,
             vel,
-            recent_collisions: -4.0,
         });
     }
 }
 
-pub fn add_planet<const LEVEL: usize>(
+pub fn add_planet(
     commands: &mut Commands,
-    pos: Vec3,
-    mass: f32,
-    radius: f32,
+    level: usize,
+    index: usize,
+    planet: &jamhacks_physics::Planet,
     mesh: &Handle<Mesh>,
     material: Handle<StandardMaterial>,
-) {
+) -> Entity {
     commands.spawn_bundle(PbrBundle {
         mesh: mesh.clone(),
         material,
-        transform: Transform::from_xyz(pos.x, pos.y, pos.z)
-            .with_scale(Vec3::splat(radius)),
+        transform: Transform::from_translation(planet.pos)
+            .with_scale(Vec3::splat(planet.radius)),
         ..default()
     })
         .insert(Planet {
-            mass,
-            radius,
+            index,
+            mass: planet.mass,
+            radius: planet.radius,
+            restitution: planet.restitution,
+            friction: planet.friction,
+            vel: planet.vel,
+            motion: planet.motion.clone(),
         })
-        // .insert(Dynamics {
-        //     acc: Vec3::ZERO,
-        //     vel: Vec3::ZERO,
-        // })
         .insert(GameElement)
-        .insert(LevelId::<LEVEL>);
+        .insert(LevelId(level))
+        .id()
 }
 
-pub fn add_target<const LEVEL: usize>(
+pub fn add_target(
     commands: &mut Commands,
+    level: usize,
     pos: Vec3,
     mesh: &Handle<Mesh>,
     material: &Handle<StandardMaterial>,
+    planet: Entity,
+    center_of_planet: Vec3,
 ) {
     commands.spawn_bundle(PbrBundle {
         mesh: mesh.clone(),
@@ -332,41 +494,73 @@ pub fn add_target<const LEVEL: usize>(
         ..default()
     })
         .insert(Target)
+        .insert(OnGround {
+            planet,
+            offset: pos - center_of_planet,
+        })
         .insert(GameElement)
-        .insert(LevelId::<LEVEL>);
+        .insert(LevelId(level));
 }
 
-fn update_timestep(
-    mut delta_time: ResMut<DeltaTime>,
-    time: Res<Time>,
+// Everything in the physics stage reads `Transform` rather than
+// `GlobalTransform`, since the stage can run several times before
+// transforms are propagated.
+fn move_planets(
+    settings: Res<PhysicsSettings>,
+    integrator: Res<IntegratorKind>,
+    n_body: Res<NBodyMode>,
+    mut clock: ResMut<PhysicsClock>,
+    mut planets: Query<(&mut Planet, &mut Transform)>,
+) {
+    let mut world = world_from(planets.iter(), *integrator, clock.time);
+    world.n_body = n_body.0;
+    world.advance(settings.step());
+    clock.time = world.time;
+    clock.steps += 1;
+
+    for (mut planet, mut transform) in planets.iter_mut() {
+        let moved = &world.planets[planet.index];
+        transform.translation = moved.pos;
+        planet.vel = moved.vel;
+    }
+}
+
+fn ride_planets(
+    planets: Query<&Transform, With<Planet>>,
+    mut riders: Query<(&mut Transform, &OnGround), Without<Planet>>,
 ) {
-    delta_time.time *= 0.8;
-    delta_time.time += 0.2 * time.delta_seconds();
+    for (mut transform, ground) in riders.iter_mut() {
+        if let Ok(planet) = planets.get(ground.planet) {
+            transform.translation = planet.translation + ground.offset;
+        }
+    }
 }
 
 fn update_acc(
-    query_pos: Query<(&GlobalTransform, &Planet)>,
-    mut query_acc: Query<(&GlobalTransform, &mut Dynamics)>,
+    query_pos: Query<(&Planet, &Transform)>,
+    mut query_acc: Query<(&Transform, &mut Dynamics)>,
+    integrator: Res<IntegratorKind>,
+    clock: Res<PhysicsClock>,
 ) {
+    let world = world_from(query_pos.iter(), *integrator, clock.time);
     for (outer_pos, mut acc) in query_acc.iter_mut() {
-        acc.acc = Vec3::ZERO;
-        for (inner_pos, inner_gravity) in query_pos.iter() {
-            let f = acc_of(inner_gravity.mass, outer_pos.translation, inner_pos.translation);
-
-            acc.acc += f;
-        }
+        acc.acc = world.acceleration_at(outer_pos.translation);
     }
 }
 
 fn update_pos_vel( // in the name
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut Dynamics)>,
+    settings: Res<PhysicsSettings>,
+    integrator: Res<IntegratorKind>,
+    clock: Res<PhysicsClock>,
+    planets: Query<(&Planet, &Transform), Without<Dynamics>>,
+    mut query: Query<(&GravityAffected, &mut Transform, &mut Dynamics)>,
 ) {
-    let delta = time.delta().as_secs_f32();
-    for (mut pos, mut acc) in query.iter_mut() {
-        let delta_vel = acc.acc * delta;
-        acc.vel += delta_vel;
-        pos.translation += acc.vel * delta;
+    let world = world_from(planets.iter(), *integrator, clock.time);
+    for (affected, mut pos, mut dynamics) in query.iter_mut() {
+        let mut ball = dynamics.to_ball(pos.translation, affected.radius);
+        world.integrate(&mut ball, settings.step());
+        pos.translation = ball.pos;
+        dynamics.vel = ball.vel;
     }
 }
 
@@ -375,50 +569,43 @@ fn collide(
         Entity,
         &GravityAffected,
         &mut Dynamics,
-        &GlobalTransform,
         &mut Transform
     )>,
     statics: Query<(
+        Entity,
         &Planet,
-        &GlobalTransform,
-    )>,
+        &Transform,
+    ), Without<Dynamics>>,
+    integrator: Res<IntegratorKind>,
+    clock: Res<PhysicsClock>,
     mut commands: Commands,
     mut camera_state: ResMut<CameraState>,
 ) { //checking for collisions between ball and planet
-    for (ball_entity, ball, mut ball_dyn, ball_pos, mut ball_transform) in query.iter_mut() {
-        ball_dyn.recent_collisions *= 0.8;
-        for (planet, planet_transform) in statics.iter() {
-            let delta = ball_pos.translation - planet_transform.translation;
-
-            let needed_dist = ball.radius + planet.radius;
-
-            if delta.length() - needed_dist < 0.0 && ball_dyn.recent_collisions > -2.0 {
-                println!("Collided: {}!", ball_dyn.recent_collisions);
-                ball_dyn.recent_collisions += 1.0;
-                let displacement = planet_transform.translation - ball_transform.translation;
-                let displacement = displacement.normalize() * needed_dist;
-
-                ball_transform.translation = planet_transform.translation - displacement;
-
-                let incident = ball_dyn.vel.normalize();
-                let normal = delta.normalize();
+    let world = world_from(statics.iter().map(|(_, planet, transform)| (planet, transform)), *integrator, clock.time);
+    for (ball_entity, affected, mut ball_dyn, mut ball_transform) in query.iter_mut() {
+        let mut ball = ball_dyn.to_ball(ball_transform.translation, affected.radius);
+        let collision = world.collide(&mut ball);
 
-                let reflected = incident - 2.0 * normal.dot(incident) * normal;
+        ball_transform.translation = ball.pos;
+        ball_dyn.vel = ball.vel;
 
-                ball_dyn.vel = reflected * ball_dyn.vel.length() * 0.75;
+        if let Collision::Landed(index) = collision {
+            let planet = statics
+                .iter()
+                .find(|(_, planet, _)| planet.index == index)
+                .map(|(entity, _, _)| entity)
+                .unwrap();
 
-                if ball_dyn.recent_collisions > 2.0 {
-                    commands.entity(ball_entity)
-                        .remove::<Dynamics>()
-                        .insert(OnGround {
-                            center_of_planet: planet_transform.translation
-                        });
+            commands.entity(ball_entity)
+                .remove::<Dynamics>()
+                .insert(OnGround {
+                    planet,
+                    offset: ball.pos - world.planets[index].pos,
+                });
 
-                    *camera_state = CameraState::Around {
-                        pos: planet_transform.translation,
-                    };
-                }
-            }
+            *camera_state = CameraState::Around {
+                planet,
+            };
         }
     }
 }
@@ -429,6 +616,7 @@ fn camera_movement(
     target: Res<TargetedPlanet>,
     mut query: Query<(&mut Transform, &mut CameraTag)>,
     ball: Query<&GlobalTransform, With<MainBall>>,
+    planets: Query<&GlobalTransform, With<Planet>>,
     time: Res<Time>,
     state: Res<CameraState>,
 ) {
@@ -450,7 +638,8 @@ fn camera_movement(
             camera.rig.driver_mut::<Position>().position = ball.translation;
             camera.rig.driver_mut::<LookAt>().target = ball.translation;
         },
-        CameraState::Around { pos } => {
+        CameraState::Around { planet } => {
+            let pos = planets.get(planet).map_or(ball.translation, |x| x.translation);
             camera.rig.driver_mut::<Position>().position = pos;
             camera.rig.driver_mut::<LookAt>().target = pos;
         },
@@ -471,8 +660,13 @@ fn aim(
     camera: Query<&Transform, With<CameraTag>>,
     gravity_sources: Query<(&Planet, &Transform)>,
     mut ball: Query<(Entity, &Transform, &OnGround), (With<MainBall>, Without<Dynamics>)>,
-    time: Res<DeltaTime>,
+    settings: Res<PhysicsSettings>,
+    integrator: Res<IntegratorKind>,
+    n_body: Res<NBodyMode>,
+    clock: Res<PhysicsClock>,
     mut camera_state: ResMut<CameraState>,
+    mut scorecard: ResMut<Scorecard>,
+    mut launched: EventWriter<Launched>,
 ) {
     let (ball_entity, ball_pos, ground) = if let Some(x) = ball.iter_mut().next() {
         x
@@ -492,27 +686,38 @@ fn aim(
 
     let camera = camera.iter().next().unwrap();
 
+    let (planet, planet_transform) = match gravity_sources.get(ground.planet) {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+
     spheres
         .iter()
         .for_each(|x| commands.entity(x).despawn());
 
     if clicked.just_released(MouseButton::Right) {
         if let Some(start) = input.start {
-            let vel = vel_from_delta(
+            let vel = planet.vel + vel_from_delta(
                 input.cursor_pos - start,
                 camera,
                 ball_pos.translation,
-                ground.center_of_planet,
+                planet_transform.translation,
             );
             commands.entity(ball_entity)
                 .insert(Dynamics {
                     vel,
                     acc: Vec3::ZERO,
-                    recent_collisions: -4.0,
                 })
+                .insert(LastRest(*ground))
                 .remove::<OnGround>();
 
             *camera_state = CameraState::Follow;
+            scorecard.strokes += 1;
+            launched.send(Launched {
+                start: ball_pos.translation,
+                vel,
+                steps: clock.steps,
+            });
 
             input.start = None;
         }
@@ -521,28 +726,44 @@ fn aim(
     if let Some(pos) = input.start {
         let delta = input.cursor_pos - pos;
 
-        let vel = vel_from_delta(
+        let vel = planet.vel + vel_from_delta(
             delta,
             camera,
             ball_pos.translation,
-            ground.center_of_planet,
+            planet_transform.translation,
         );
 
-        let positions = simulate_ball(
-            gravity_sources,
+        let trajectory = simulate_ball(
+            &gravity_sources,
             ball_pos.translation,
             vel,
-            30,
-            12,
-            time.time
+            (PREVIEW_SECONDS / settings.step()) as usize,
+            12 * settings.substeps as usize,
+            settings.step(),
+            *integrator,
+            n_body.0,
+            clock.time,
         );
 
-        for (i, pos) in positions.iter().copied().enumerate() {
+        let positions = &trajectory.points;
+        let markers = positions
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| (*pos, &projected_resources.material, 0.05 * (1.0 - i as f32 / positions.len() as f32)))
+            .chain(trajectory.bounces
+                .iter()
+                .enumerate()
+                .map(|(i, pos)| (*pos, &projected_resources.bounce_material, 0.08 - 0.015 * i as f32)))
+            .chain(trajectory.rest
+                .iter()
+                .map(|pos| (*pos, &projected_resources.rest_material, 0.1)));
+
+        for (pos, material, scale) in markers {
             commands.spawn_bundle(PbrBundle {
                 mesh: projected_resources.mesh.clone(),
-                material: projected_resources.material.clone(),
-                transform: Transform::from_xyz(pos.x, pos.y, pos.z)
-                    .with_scale(0.05 * Vec3::splat(1.0 - i as f32 / positions.len() as f32)),
+                material: material.clone(),
+                transform: Transform::from_translation(pos)
+                    .with_scale(Vec3::splat(scale)),
                 ..default()
             })
                 .insert(ProjectedSphere);
@@ -550,6 +771,12 @@ fn aim(
     }
 }
 
+/// How many bounces the aim preview draws the path through.
+const PREVIEW_BOUNCES: usize = 3;
+
+/// How long the aim preview follows the ball looking for where it stops.
+const PREVIEW_SECONDS: f32 = 20.0;
+
 fn vel_from_delta(
     delta: Vec2,
     transform: &Transform,
@@ -577,52 +804,28 @@ fn vel_from_delta(
     (delta.x * dir_x + delta.y * dir_y) / 50.0
 }
 
+/// Steps a ball forward exactly as the physics stage would, for at most
+/// `steps` steps, keeping a point of the path every `steps_between` steps.
 fn simulate_ball(
-    gravity: Query<(&Planet, &Transform)>,
+    gravity: &Query<(&Planet, &Transform)>,
     start: Vec3,
     vel: Vec3,
-    num: usize,
+    steps: usize,
     steps_between: usize,
     timestep: f32,
-) -> Vec<Vec3> {
-    let mut pos = start;
-    let mut vel = vel;
-    let mut result = Vec::new();
-
-    let planets = gravity
-        .iter()
-        .map(|(planet, transform)| (planet.mass, planet.radius, transform.translation))
-        .collect::<Vec<_>>();
-
-    for _ in 0..num {
-        for _ in 0..steps_between {
-            let mut acc_sum = Vec3::ZERO;
-
-            let mut inside = false;
-
-            planets
-                .iter()
-                .copied()
-                .for_each(|(p_mass, p_rad, p_pos)| {
-                    acc_sum += acc_of(p_mass, pos, p_pos);
-                    inside |= (pos - p_pos).length() < p_rad;
-                });
-
-            if inside {
-                return result;
-            }
-
-            vel += acc_sum * timestep;
-
-            pos += vel * timestep;
-        }
-
-        result.push(pos);
-    }
-
-    result
-}
-
-fn acc_of(m2: f32, p1: Vec3, p2: Vec3) -> Vec3 {
-    (p2 - p1).normalize() * GRAVITY * m2 / (p1 - p2).length_squared()
+    integrator: IntegratorKind,
+    n_body: Option<NBody>,
+    time: f32,
+) -> Trajectory {
+    let mut world = world_from(gravity.iter(), integrator, time);
+    world.n_body = n_body;
+
+    let mut trajectory = world.predict(Ball::new(start, vel, BALL_RAD), steps, timestep, PREVIEW_BOUNCES);
+    trajectory.points = trajectory.points
+        .into_iter()
+        .skip(steps_between - 1)
+        .step_by(steps_between)
+        .collect();
+
+    trajectory
 }
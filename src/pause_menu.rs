@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use crate::levels::{go_to_level, CurrentLevel};
+use crate::setup::*;
+use crate::start_menu::{spawn_panel, UiAssets};
+
+/// Escape pauses the game under a menu. The physics stage only runs in
+/// `GameState::Game`, so everything stays put until it's resumed.
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(toggle_pause)
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(add_pause_menu))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(handle_pause_buttons))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(crate::despawn::<PauseMenu>));
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Copy, Clone)]
+enum PauseButton {
+    Resume,
+    Restart,
+    MainMenu,
+}
+
+/// Runs in every state rather than on update of `Game` and `Paused`, which
+/// would both see the same key press in the frame the state changes. Either
+/// this or `touch_target` can find a transition already queued, and both let
+/// the other one win.
+fn toggle_pause(keys: Res<Input<KeyCode>>, mut game: ResMut<State<GameState>>) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    // Ignores the key if another transition is already queued this frame,
+    // e.g. the ball reaching the target in the physics stage.
+    let _ = match game.current() {
+        GameState::Game => game.push(GameState::Paused),
+        GameState::Paused => game.pop(),
+        _ => Ok(()),
+    };
+}
+
+fn add_pause_menu(mut commands: Commands, assets: Res<AssetServer>, ui_assets: Res<UiAssets>) {
+    spawn_panel(
+        &mut commands,
+        &assets,
+        &ui_assets,
+        PauseMenu,
+        "Paused",
+        String::new(),
+        &[
+            ("Resume", PauseButton::Resume),
+            ("Restart level", PauseButton::Restart),
+            ("Main menu", PauseButton::MainMenu),
+        ],
+    );
+}
+
+fn handle_pause_buttons(
+    buttons: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    mut game: ResMut<State<GameState>>,
+    mut level: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+) {
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| *button);
+
+    match clicked {
+        Some(PauseButton::Resume) => {
+            game.pop().unwrap();
+        }
+        Some(PauseButton::Restart) => {
+            game.pop().unwrap();
+            let index = current.0;
+            go_to_level(index, &mut current, &mut level);
+        }
+        Some(PauseButton::MainMenu) => {
+            game.replace(GameState::Splash).unwrap();
+            level.set(LevelState::None).unwrap();
+        }
+        None => {}
+    }
+}
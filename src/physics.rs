@@ -0,0 +1,171 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use jamhacks_physics::{IntegratorKind, NBody, World};
+use crate::setup::*;
+
+/// The longest frame the physics will try to catch up on, so a hitch doesn't
+/// turn into hundreds of steps in one frame.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct PhysicsStage;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub enum PhysicsSystem {
+    Planets,
+    Acceleration,
+    Integrate,
+    Collide,
+    Target,
+}
+
+/// How often the physics ticks, and how many steps each tick is split into.
+/// Every step is exactly `step()` seconds long, both in the game and in the
+/// aiming preview.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsSettings {
+    pub hz: f32,
+    pub substeps: u32,
+}
+
+impl PhysicsSettings {
+    pub fn tick(&self) -> f32 {
+        1.0 / self.hz
+    }
+
+    pub fn step(&self) -> f32 {
+        self.tick() / self.substeps as f32
+    }
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            hz: 60.0,
+            substeps: 4,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PhysicsClock {
+    /// Simulated time since the level was loaded, which moving planets
+    /// follow.
+    pub time: f32,
+    /// Steps taken since the level was loaded. Replaying that many steps of
+    /// the level from scratch gets the planets back to exactly where they
+    /// are now.
+    pub steps: u64,
+    accumulator: f32,
+    pending: u32,
+    looping: bool,
+}
+
+/// Whether free planets pull on each other, as set by the current level.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NBodyMode(pub Option<NBody>);
+
+/// The time warps the player can step through, slowest first.
+pub const WARPS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// How many simulated seconds pass per real second.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimeWarp {
+    /// Index into `WARPS` of the warp the player picked.
+    pub index: usize,
+    /// Runs at the fastest warp regardless, while skipping a long orbit.
+    pub fast_forward: bool,
+}
+
+impl TimeWarp {
+    pub fn factor(&self) -> f32 {
+        if self.fast_forward {
+            WARPS[WARPS.len() - 1]
+        } else {
+            WARPS[self.index]
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.index = (self.index + 1).min(WARPS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.index = self.index.saturating_sub(1);
+    }
+}
+
+impl Default for TimeWarp {
+    fn default() -> Self {
+        Self {
+            index: WARPS.iter().position(|&warp| warp == 1.0).unwrap(),
+            fast_forward: false,
+        }
+    }
+}
+
+pub fn reset_clock(mut clock: ResMut<PhysicsClock>) {
+    clock.time = 0.0;
+    clock.steps = 0;
+}
+
+/// Runs the physics stage once per step, as many times as the real time
+/// accumulated since the last frame allows, sped up or slowed down by the
+/// time warp.
+pub fn physics_tick(
+    time: Res<Time>,
+    settings: Res<PhysicsSettings>,
+    warp: Res<TimeWarp>,
+    mut clock: ResMut<PhysicsClock>,
+    state: Res<State<GameState>>,
+) -> ShouldRun {
+    if *state.current() != GameState::Game {
+        // Keep `time` so planets carry on from where they were if the game
+        // is only paused underneath a menu.
+        clock.accumulator = 0.0;
+        clock.pending = 0;
+        clock.looping = false;
+        return ShouldRun::No;
+    }
+
+    if !clock.looping {
+        let factor = warp.factor();
+        clock.accumulator = (clock.accumulator + time.delta_seconds() * factor)
+            .min(MAX_FRAME_TIME * factor.max(1.0));
+    }
+
+    if clock.pending == 0 {
+        if clock.accumulator < settings.tick() {
+            clock.looping = false;
+            return ShouldRun::No;
+        }
+        clock.accumulator -= settings.tick();
+        clock.pending = settings.substeps;
+    }
+
+    clock.pending -= 1;
+    clock.looping = true;
+    ShouldRun::YesAndCheckAgain
+}
+
+/// Snapshot of the planets at `time`, for the physics library to work on.
+/// Planets are put back in level order, so orbits can find their parents.
+pub fn world_from<'a>(
+    planets: impl Iterator<Item = (&'a Planet, &'a Transform)>,
+    integrator: IntegratorKind,
+    time: f32,
+) -> World {
+    let mut planets = planets
+        .map(|(planet, transform)| (planet.index, planet.to_physics(transform.translation)))
+        .collect::<Vec<_>>();
+    planets.sort_by_key(|(index, _)| *index);
+
+    World {
+        planets: planets.into_iter().map(|(_, planet)| planet).collect(),
+        balls: Vec::new(),
+        integrator,
+        time,
+        n_body: None,
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
+use crate::replay::Shot;
+
+/// Best result on a level. Strokes and time are kept separately, they don't
+/// have to come from the same run.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Best {
+    pub strokes: u32,
+    pub time: f32,
+}
+
+/// Best result on the daily challenge of `date`. Only one day is kept, a new
+/// day starts afresh.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyBest {
+    pub date: String,
+    pub best: Best,
+}
+
+/// What the player has achieved so far, keyed by level name so it survives
+/// levels being reordered in the pack.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    #[serde(default)]
+    pub unlocked: BTreeSet<String>,
+    #[serde(default)]
+    pub best: BTreeMap<String, Best>,
+    /// The shots of the run with the fewest strokes on each level.
+    #[serde(default)]
+    pub solutions: BTreeMap<String, Vec<Shot>>,
+    #[serde(default)]
+    pub daily: Option<DailyBest>,
+}
+
+impl Progress {
+    pub fn record(&mut self, level: &str, strokes: u32, time: f32) {
+        let best = self.best.entry(level.to_string()).or_insert(Best { strokes, time });
+        best.strokes = best.strokes.min(strokes);
+        best.time = best.time.min(time);
+    }
+
+    pub fn record_daily(&mut self, date: &str, strokes: u32, time: f32) {
+        match &mut self.daily {
+            Some(daily) if daily.date == date => {
+                daily.best.strokes = daily.best.strokes.min(strokes);
+                daily.best.time = daily.best.time.min(time);
+            }
+            _ => {
+                self.daily = Some(DailyBest {
+                    date: date.to_string(),
+                    best: Best { strokes, time },
+                })
+            }
+        }
+    }
+
+    pub fn daily_best(&self, date: &str) -> Option<Best> {
+        self.daily.as_ref().filter(|daily| daily.date == date).map(|daily| daily.best)
+    }
+
+    /// Keeps `shots` if they finished the level in fewer strokes than the
+    /// solution already kept.
+    pub fn record_solution(&mut self, level: &str, shots: &[Shot]) {
+        if shots.is_empty() {
+            return;
+        }
+        let better = self.solutions
+            .get(level)
+            .is_none_or(|solution| shots.len() < solution.len());
+        if better {
+            self.solutions.insert(level.to_string(), shots.to_vec());
+        }
+    }
+
+    pub fn solution(&self, level: &str) -> Option<&[Shot]> {
+        self.solutions.get(level).map(Vec::as_slice)
+    }
+
+    pub fn unlock(&mut self, level: &str) {
+        if !self.unlocked.contains(level) {
+            self.unlocked.insert(level.to_string());
+        }
+    }
+
+    pub fn best(&self, level: &str) -> Option<Best> {
+        self.best.get(level).copied()
+    }
+
+    /// The first level is always open, every other one once it's been
+    /// unlocked or the level before it has been finished.
+    pub fn is_unlocked(&self, level: &str, previous: Option<&str>) -> bool {
+        self.unlocked.contains(level)
+            || previous.is_none_or(|previous| self.best.contains_key(previous))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::Vec3;
+    use jamhacks_physics::IntegratorKind;
+    use crate::physics::PhysicsSettings;
+
+    fn shots(n: usize) -> Vec<Shot> {
+        (0..n)
+            .map(|i| Shot {
+                level: "one".to_string(),
+                steps: i as u64 * 100,
+                start: Vec3::ZERO,
+                vel: Vec3::X,
+                integrator: IntegratorKind::default(),
+                settings: PhysicsSettings::default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn record_keeps_the_best_of_each() {
+        let mut progress = Progress::default();
+        progress.record("one", 4, 10.0);
+        progress.record("one", 3, 20.0);
+        progress.record("one", 5, 8.0);
+        assert_eq!(progress.best("one"), Some(Best { strokes: 3, time: 8.0 }));
+        assert_eq!(progress.best("two"), None);
+    }
+
+    #[test]
+    fn unlocking() {
+        let mut progress = Progress::default();
+        assert!(progress.is_unlocked("one", None));
+        assert!(!progress.is_unlocked("two", Some("one")));
+
+        progress.record("one", 3, 10.0);
+        assert!(progress.is_unlocked("two", Some("one")));
+
+        progress.unlock("four");
+        progress.unlock("four");
+        assert!(progress.is_unlocked("four", Some("three")));
+        assert_eq!(progress.unlocked.len(), 1);
+    }
+
+    #[test]
+    fn record_solution_keeps_the_fewest_strokes() {
+        let mut progress = Progress::default();
+        progress.record_solution("one", &[]);
+        assert_eq!(progress.solution("one"), None);
+
+        progress.record_solution("one", &shots(3));
+        progress.record_solution("one", &shots(4));
+        assert_eq!(progress.solution("one").map(<[Shot]>::len), Some(3));
+
+        progress.record_solution("one", &shots(2));
+        assert_eq!(progress.solution("one"), Some(&shots(2)[..]));
+    }
+
+    #[test]
+    fn daily_best_is_only_for_its_day() {
+        let mut progress = Progress::default();
+        progress.record_daily("2022-05-14", 4, 10.0);
+        progress.record_daily("2022-05-14", 3, 12.0);
+        assert_eq!(progress.daily_best("2022-05-14"), Some(Best { strokes: 3, time: 10.0 }));
+
+        progress.record_daily("2022-05-15", 5, 20.0);
+        assert_eq!(progress.daily_best("2022-05-14"), None);
+        assert_eq!(progress.daily_best("2022-05-15"), Some(Best { strokes: 5, time: 20.0 }));
+    }
+}
@@ -0,0 +1,416 @@
+use std::sync::{Arc, Mutex};
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use jamhacks_physics::solve::Sweep;
+use crate::daily::Date;
+use crate::levels::{game_sweep, CurrentLevel, Level, Levels, PointOfInterest, SavedPlanet};
+use crate::score::Scorecard;
+use crate::setup::*;
+use crate::start_menu::{set_button_label, start_game};
+
+/// Layouts tried before giving up on a seed.
+const ATTEMPTS: usize = 30;
+
+/// Closest the surfaces of two planets get.
+const MIN_GAP: f32 = 1.5;
+
+const COLOURS: [[f32; 3]; 5] = [
+    [0.9, 0.6, 0.4],
+    [0.5, 0.7, 1.0],
+    [0.6, 0.9, 0.5],
+    [0.8, 0.6, 1.0],
+    [1.0, 0.9, 0.6],
+];
+
+/// Anti-grav planets are green, as in "Repulsor".
+const REPULSOR_COLOUR: [f32; 3] = [0.0, 1.0, 0.0];
+
+/// Adds "Random level" to the main menu, which makes up a new level at the
+/// difficulty next to it and plays it on its own. Also makes the levels for
+/// `DailyPlugin`, which needs this one added first.
+pub struct RandomLevelPlugin;
+
+impl Plugin for RandomLevelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<RandomLevel>()
+            .init_resource::<Generator>()
+            .add_event::<Generated>()
+            .add_system_set(SystemSet::on_update(GameState::Splash)
+                .with_system(handle_random_buttons)
+                .with_system(finish_generating)
+                .with_system(play_random_level.after(finish_generating))
+                .with_system(reset_random_button.after(finish_generating))
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Splash).with_system(stop_generating));
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub enum RandomButton {
+    Play,
+    Difficulty,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn value(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.0,
+            Difficulty::Medium => 0.5,
+            Difficulty::Hard => 1.0,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Difficulty: easy",
+            Difficulty::Medium => "Difficulty: medium",
+            Difficulty::Hard => "Difficulty: hard",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RandomLevel {
+    pub difficulty: Difficulty,
+}
+
+/// Which menu button a level is being made for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Requester {
+    Random,
+    Daily(Date),
+}
+
+/// Sent when a level has been made, with `None` if the seed didn't give one.
+pub(crate) struct Generated {
+    pub(crate) requester: Requester,
+    pub(crate) level: Option<Handle<Level>>,
+}
+
+/// Makes levels on the async compute pool, so the menu keeps drawing while
+/// the solver works. Only one at a time, so two buttons can't both start a
+/// game.
+pub(crate) struct Generator {
+    pool: AsyncComputeTaskPool,
+    job: Option<Job>,
+}
+
+struct Job {
+    requester: Requester,
+    /// Filled in by the task when it's done. Tasks on the web all run on the
+    /// one thread and can't be polled, so the level comes back through here
+    /// rather than through the `Task`.
+    result: Arc<Mutex<Option<Option<Level>>>>,
+}
+
+impl FromWorld for Generator {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            pool: world.resource::<AsyncComputeTaskPool>().clone(),
+            job: None,
+        }
+    }
+}
+
+impl Generator {
+    pub(crate) fn is_busy(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Starts making a level from `seed` for `requester`. Does nothing if a
+    /// level is already being made.
+    pub(crate) fn start(&mut self, requester: Requester, seed: u64, difficulty: f32) {
+        if self.is_busy() {
+            return;
+        }
+        let result = Arc::new(Mutex::new(None));
+        let slot = Arc::clone(&result);
+        self.pool.spawn(async move {
+            let level = generate(seed, difficulty).map(|level| match requester {
+                Requester::Random => level,
+                Requester::Daily(date) => Level {
+                    name: format!("Daily {}", date),
+                    ..level
+                },
+            });
+            if level.is_none() {
+                warn!("Couldn't generate a level from seed {}", seed);
+            }
+            *slot.lock().unwrap() = Some(level);
+        }).detach();
+        self.job = Some(Job { requester, result });
+    }
+}
+
+/// A quicker search than `game_sweep`, so making a level doesn't hold the
+/// menu up for long. Levels it can't finish in two strokes get thrown away,
+/// so it only has to find one way round, not the best.
+fn generator_sweep() -> Sweep {
+    Sweep {
+        directions: 48,
+        speeds: 8,
+        max_strokes: 2,
+        frontier: 4,
+        examples: 1,
+        steps: 12 * 240,
+        ..game_sweep()
+    }
+}
+
+/// Makes a level from `seed` that the solver can finish, with par a stroke
+/// over the fewest it needed. `difficulty` goes from 0, a few big planets
+/// close together with the goal on the nearest, to 1, more and smaller
+/// planets spread further apart with repulsors between them and the goal on
+/// the furthest. The same seed and difficulty always give the same level.
+pub fn generate(seed: u64, difficulty: f32) -> Option<Level> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let sweep = generator_sweep();
+
+    (0..ATTEMPTS).find_map(|_| {
+        let mut level = layout(&mut rng, difficulty.clamp(0.0, 1.0))?;
+        let strokes = level.solve(&sweep).min_strokes?;
+        level.name = format!("Random {}", seed);
+        level.par = strokes + 1;
+        Some(level)
+    })
+}
+
+/// Lays out planets and picks a start and goal, without checking the level
+/// can be finished. Gives up if the planets won't fit.
+fn layout(rng: &mut impl Rng, difficulty: f32) -> Option<Level> {
+    let attractors = 2 + (difficulty * 3.0).round() as usize + rng.gen_range(0..=1);
+    let repulsors = rng.gen_range(0..=(difficulty * 3.0).round() as usize);
+    let spacing = 3.0 + 4.0 * difficulty;
+    let mut planets: Vec<SavedPlanet> = Vec::new();
+
+    let mut tries = 0;
+    while planets.len() < attractors {
+        tries += 1;
+        if tries > 20 * attractors {
+            return None;
+        }
+        let radius = rng.gen_range(0.9..1.5) - 0.3 * difficulty;
+        // Keeps gravity at the surface about what it is on the made levels.
+        let mass = radius * radius * rng.gen_range(400.0..600.0);
+        let pos = match planets.len() {
+            0 => Vec3::ZERO,
+            n => {
+                let near = &planets[rng.gen_range(0..n)];
+                near.pos + direction(rng) * (near.radius + radius + spacing * rng.gen_range(0.8..1.25))
+            }
+        };
+        if fits(&planets, pos, radius) {
+            let colour = COLOURS[rng.gen_range(0..COLOURS.len())];
+            planets.push(SavedPlanet {
+                colour: Vec3::from(colour),
+                ..SavedPlanet::new(pos, mass, radius)
+            });
+        }
+    }
+
+    // Repulsors go somewhere between two planets, to push shots off line.
+    tries = 0;
+    while planets.len() < attractors + repulsors && tries < 20 {
+        tries += 1;
+        let a = &planets[rng.gen_range(0..attractors)];
+        let b = &planets[rng.gen_range(0..attractors)];
+        let radius = rng.gen_range(0.3..0.5);
+        let pos = a.pos.lerp(b.pos, rng.gen_range(0.3..0.7)) + direction(rng) * rng.gen_range(0.5..2.0);
+        if fits(&planets, pos, radius) {
+            let mass = -rng.gen_range(100.0..200.0) * (0.5 + difficulty);
+            planets.push(SavedPlanet {
+                colour: Vec3::from(REPULSOR_COLOUR),
+                ..SavedPlanet::new(pos, mass, radius)
+            });
+        }
+    }
+
+    // The harder the level, the further the goal is from the start.
+    let mut others = (1..attractors).collect::<Vec<_>>();
+    others.sort_by(|a, b| planets[*a].pos.length().total_cmp(&planets[*b].pos.length()));
+    let goal = others[((others.len() - 1) as f32 * difficulty).round() as usize];
+
+    Some(Level {
+        name: String::new(),
+        planets,
+        start: PointOfInterest {
+            planet: 0,
+            direction: direction(rng),
+        },
+        goal: PointOfInterest {
+            planet: goal,
+            direction: direction(rng),
+        },
+        par: 0,
+        n_body: None,
+    })
+}
+
+fn fits(planets: &[SavedPlanet], pos: Vec3, radius: f32) -> bool {
+    planets
+        .iter()
+        .all(|planet| (planet.pos - pos).length() > planet.radius + radius + MIN_GAP)
+}
+
+/// A random unit vector, evenly spread over the sphere.
+fn direction(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let v = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        if (0.01..=1.0).contains(&v.length_squared()) {
+            return v.normalize();
+        }
+    }
+}
+
+fn handle_random_buttons(
+    buttons: Query<(&Interaction, &RandomButton, &Children), Changed<Interaction>>,
+    images: Query<&Children, With<UiImage>>,
+    mut texts: Query<&mut Text>,
+    mut random: ResMut<RandomLevel>,
+    mut generator: ResMut<Generator>,
+) {
+    for (interaction, button, children) in buttons.iter() {
+        if *interaction != Interaction::Clicked || generator.is_busy() {
+            continue;
+        }
+
+        let label = match button {
+            RandomButton::Play => {
+                generator.start(Requester::Random, rand::thread_rng().gen(), random.difficulty.value());
+                "Generating..."
+            }
+            RandomButton::Difficulty => {
+                random.difficulty = random.difficulty.next();
+                random.difficulty.label()
+            }
+        };
+        set_button_label(children, &images, &mut texts, label);
+    }
+}
+
+/// Checks on the level being made, and sends `Generated` once it's done.
+fn finish_generating(
+    mut generator: ResMut<Generator>,
+    mut level_assets: ResMut<Assets<Level>>,
+    mut generated: EventWriter<Generated>,
+) {
+    let job = match generator.job.take() {
+        Some(job) => job,
+        None => return,
+    };
+    let finished = job.result.lock().unwrap().take();
+    let level = match finished {
+        Some(level) => level,
+        None => {
+            generator.job = Some(job);
+            return;
+        }
+    };
+
+    if let Some(level) = &level {
+        info!("Generated level {} with par {}", level.name, level.par);
+    }
+    generated.send(Generated {
+        requester: job.requester,
+        level: level.map(|level| level_assets.add(level)),
+    });
+}
+
+/// Leaving the menu forgets any level still being made. The task runs on but
+/// its level goes nowhere.
+fn stop_generating(mut generator: ResMut<Generator>) {
+    generator.job = None;
+}
+
+fn play_random_level(
+    mut generated: EventReader<Generated>,
+    mut levels: ResMut<Levels>,
+    mut game_state: ResMut<State<GameState>>,
+    mut level_state: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    mut scorecard: ResMut<Scorecard>,
+) {
+    for generated in generated.iter() {
+        if let (Requester::Random, Some(level)) = (generated.requester, &generated.level) {
+            levels.custom = Some(level.clone());
+            let index = current.0;
+            start_game(index, &mut game_state, &mut level_state, &mut current, &mut scorecard);
+        }
+    }
+}
+
+/// Puts the play button back if no level could be made, so it can be tried
+/// again.
+fn reset_random_button(
+    mut generated: EventReader<Generated>,
+    buttons: Query<(&RandomButton, &Children)>,
+    images: Query<&Children, With<UiImage>>,
+    mut texts: Query<&mut Text>,
+) {
+    for generated in generated.iter() {
+        if generated.requester != Requester::Random || generated.level.is_some() {
+            continue;
+        }
+        for (button, children) in buttons.iter() {
+            if let RandomButton::Play = button {
+                set_button_label(children, &images, &mut texts, "Random level");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_level() {
+        for (seed, difficulty) in [(1, 0.0), (7, 0.5)] {
+            let first = generate(seed, difficulty);
+            assert!(first.is_some(), "seed {} gave no level", seed);
+            assert_eq!(first, generate(seed, difficulty));
+        }
+    }
+
+    #[test]
+    fn layouts_fit_their_planets() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for difficulty in [0.0, 0.5, 1.0] {
+            for _ in 0..200 {
+                if let Some(level) = layout(&mut rng, difficulty) {
+                    for (i, a) in level.planets.iter().enumerate() {
+                        for b in &level.planets[..i] {
+                            assert!((a.pos - b.pos).length() > a.radius + b.radius);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_levels_are_valid() {
+        let level = generate(3, 1.0).unwrap();
+        assert!(level.validate().is_ok());
+        assert!(level.par >= 2);
+    }
+}
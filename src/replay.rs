@@ -0,0 +1,401 @@
+use bevy::prelude::*;
+use bevy::render::view::NoFrustumCulling;
+use serde::{Deserialize, Serialize};
+use jamhacks_physics::{Ball, IntegratorKind};
+use crate::field::line_mesh;
+use crate::levels::{CurrentLevel, Level, LevelPack, Levels, LoadResources};
+use crate::physics::PhysicsSettings;
+use crate::score::{format_time, text_style};
+use crate::setup::*;
+
+/// How long a replay follows the ball if it doesn't come to rest.
+const REPLAY_SECONDS: f32 = 60.0;
+
+/// Simulated seconds per real second while holding left or right.
+const SCRUB_SPEED: f32 = 4.0;
+
+/// Records every shot on the level, and plays the last one back with V.
+/// Space plays or pauses, left and right scrub, V or escape go back to the
+/// game. Each shot is logged as it's replayed, so it can be passed on, and
+/// shift V plays one passed on from someone else.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<Launched>()
+            .init_resource::<ShotLog>()
+            .add_system(record_shots)
+            .add_system(toggle_replay)
+            .add_system_set(SystemSet::on_enter(LevelState::Playing).with_system(clear_shots))
+            .add_system_set(SystemSet::on_enter(GameState::Replay).with_system(add_replay))
+            .add_system_set(SystemSet::on_update(GameState::Replay).with_system(scrub_replay))
+            .add_system_set(SystemSet::on_exit(GameState::Replay)
+                .with_system(restore_positions)
+                .with_system(crate::despawn::<ReplayElement>)
+            );
+    }
+}
+
+/// Sent when the ball is launched, with the number of physics steps taken on
+/// the level so far.
+pub struct Launched {
+    pub start: Vec3,
+    pub vel: Vec3,
+    pub steps: u64,
+}
+
+/// Everything needed to play a shot out again exactly as it happened.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Shot {
+    /// Name of the level it was taken on.
+    pub level: String,
+    /// Physics steps taken on the level before the shot.
+    pub steps: u64,
+    pub start: Vec3,
+    pub vel: Vec3,
+    pub integrator: IntegratorKind,
+    pub settings: PhysicsSettings,
+}
+
+/// Where everything is after one step of a replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub ball: Vec3,
+    /// In level order.
+    pub planets: Vec<Vec3>,
+}
+
+impl Shot {
+    /// Plays the level from scratch up to the shot, then follows the ball
+    /// for up to `max_steps` steps or until it comes to rest. The first frame
+    /// is the moment of the shot.
+    pub fn replay(&self, level: &Level, max_steps: usize) -> Vec<ReplayFrame> {
+        let step = self.settings.step();
+        let mut world = level.world();
+        world.integrator = self.integrator;
+        for _ in 0..self.steps {
+            world.advance(step);
+        }
+
+        world.balls.push(Ball::new(self.start, self.vel, BALL_RAD));
+        let frame = |world: &jamhacks_physics::World| ReplayFrame {
+            ball: world.balls[0].pos,
+            planets: world.planets.iter().map(|planet| planet.pos).collect(),
+        };
+
+        let mut frames = vec![frame(&world)];
+        for _ in 0..max_steps {
+            let landed = !world.step(step).is_empty();
+            frames.push(frame(&world));
+            if landed {
+                break;
+            }
+        }
+        frames
+    }
+}
+
+/// Shots taken on the level being played, oldest first.
+#[derive(Default)]
+pub struct ShotLog {
+    pub shots: Vec<Shot>,
+    /// Replayed instead of the last shot, if one's been passed on.
+    shared: Option<Shot>,
+}
+
+struct ReplayView {
+    frames: Vec<ReplayFrame>,
+    /// Seconds of simulated time per frame.
+    step: f32,
+    /// Simulated seconds since the shot.
+    at: f32,
+    playing: bool,
+    /// Where the planets and everything on them were before the replay moved
+    /// them.
+    live: Vec<(Entity, Vec3)>,
+}
+
+impl ReplayView {
+    fn duration(&self) -> f32 {
+        (self.frames.len() - 1) as f32 * self.step
+    }
+}
+
+#[derive(Component)]
+struct ReplayElement;
+
+#[derive(Component)]
+struct ReplayBall;
+
+#[derive(Component)]
+struct ReplayHud;
+
+fn clear_shots(mut log: ResMut<ShotLog>) {
+    log.shots.clear();
+}
+
+fn record_shots(
+    mut launched: EventReader<Launched>,
+    current: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    integrator: Res<IntegratorKind>,
+    settings: Res<PhysicsSettings>,
+    mut log: ResMut<ShotLog>,
+) {
+    for shot in launched.iter() {
+        let level = match levels.current(&current, &packs, &level_assets) {
+            Some(level) => level,
+            None => continue,
+        };
+
+        log.shots.push(Shot {
+            level: level.name.clone(),
+            steps: shot.steps,
+            start: shot.start,
+            vel: shot.vel,
+            integrator: *integrator,
+            settings: *settings,
+        });
+    }
+}
+
+/// Runs in every state for the same reason as `toggle_pause`. The ball
+/// reaching the target is checked in the physics stage, before this, so if
+/// that's already queued a transition the key is ignored.
+fn toggle_replay(
+    keys: Res<Input<KeyCode>>,
+    mut log: ResMut<ShotLog>,
+    mut game: ResMut<State<GameState>>,
+    current: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+) {
+    let replay = match game.current() {
+        GameState::Game if keys.just_pressed(KeyCode::V) => {
+            if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+                let level = levels.current(&current, &packs, &level_assets);
+                log.shared = level.and_then(|level| match read_shared_shot(level) {
+                    Ok(shot) => Some(shot),
+                    Err(err) => {
+                        warn!("Couldn't play shared shot: {}", err);
+                        None
+                    }
+                });
+                log.shared.is_some()
+            } else {
+                log.shared = None;
+                !log.shots.is_empty()
+            }
+        }
+        _ => false,
+    };
+
+    // Ignores the key if another transition is already queued this frame.
+    let _ = match game.current() {
+        GameState::Game if replay => game.push(GameState::Replay),
+        GameState::Replay if keys.any_just_pressed([KeyCode::V, KeyCode::Escape]) => game.pop(),
+        _ => Ok(()),
+    };
+}
+
+/// A shot someone else took on `level`, as logged when they replayed it.
+fn read_shared_shot(level: &Level) -> Result<Shot, anyhow::Error> {
+    let text = shared::read().ok_or_else(|| anyhow::anyhow!("nothing in {}", shared::PLACE))?;
+    let shot = ron::de::from_str::<Shot>(text.trim())?;
+    if shot.level != level.name {
+        anyhow::bail!("it was taken on {}, not {}", shot.level, level.name);
+    }
+    Ok(shot)
+}
+
+fn add_replay(
+    mut commands: Commands,
+    log: Res<ShotLog>,
+    current: Res<CurrentLevel>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    level_assets: Res<Assets<Level>>,
+    resources: Res<LoadResources>,
+    assets: Res<AssetServer>,
+    live: Query<(Entity, &Transform), Or<(With<Planet>, With<OnGround>)>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let shot = log.shared.as_ref().or_else(|| log.shots.last());
+    let (shot, level) = match (shot, levels.current(&current, &packs, &level_assets)) {
+        (Some(shot), Some(level)) => (shot, level),
+        _ => return,
+    };
+
+    match ron::ser::to_string(shot) {
+        Ok(text) => info!("Replaying shot {}", text),
+        Err(err) => warn!("Couldn't write out shot: {}", err),
+    }
+
+    let step = shot.settings.step();
+    let frames = shot.replay(level, (REPLAY_SECONDS / step) as usize);
+
+    let path = frames
+        .windows(2)
+        .flat_map(|pair| [pair[0].ball, pair[1].ball])
+        .collect();
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(line_mesh(path)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.85, 0.3),
+            unlit: true,
+            ..default()
+        }),
+        ..default()
+    })
+        .insert(NoFrustumCulling)
+        .insert(ReplayElement);
+
+    commands.spawn_bundle(PbrBundle {
+        mesh: resources.player_mesh.clone(),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.85, 0.3),
+            emissive: Color::rgb(0.6, 0.45, 0.1),
+            ..default()
+        }),
+        transform: Transform::from_translation(frames[0].ball)
+            .with_scale(Vec3::splat(BALL_RAD)),
+        ..default()
+    })
+        .insert(ReplayBall)
+        .insert(ReplayElement);
+
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                bottom: Val::Px(15.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section("", text_style(&assets, 26.0), default()),
+        ..default()
+    })
+        .insert(ReplayHud)
+        .insert(ReplayElement);
+
+    commands.insert_resource(ReplayView {
+        frames,
+        step,
+        at: 0.0,
+        playing: true,
+        live: live.iter().map(|(entity, transform)| (entity, transform.translation)).collect(),
+    });
+}
+
+fn scrub_replay(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    view: Option<ResMut<ReplayView>>,
+    mut ball: Query<&mut Transform, (With<ReplayBall>, Without<Planet>, Without<OnGround>)>,
+    mut planets: Query<(&Planet, &mut Transform), Without<OnGround>>,
+    mut riders: Query<(&OnGround, &mut Transform), Without<Planet>>,
+    mut hud: Query<&mut Text, With<ReplayHud>>,
+) {
+    let mut view = match view {
+        Some(view) => view,
+        None => return,
+    };
+
+    let delta = time.delta_seconds();
+    if keys.just_pressed(KeyCode::Space) {
+        view.playing = !view.playing;
+        if view.at >= view.duration() {
+            view.at = 0.0;
+        }
+    }
+    if keys.pressed(KeyCode::Left) {
+        view.at -= delta * SCRUB_SPEED;
+        view.playing = false;
+    } else if keys.pressed(KeyCode::Right) {
+        view.at += delta * SCRUB_SPEED;
+        view.playing = false;
+    } else if view.playing {
+        view.at += delta;
+    }
+    if keys.just_pressed(KeyCode::Home) {
+        view.at = 0.0;
+    }
+    view.at = view.at.clamp(0.0, view.duration());
+    if view.at >= view.duration() {
+        view.playing = false;
+    }
+
+    let index = ((view.at / view.step) as usize).min(view.frames.len() - 1);
+    let next = (index + 1).min(view.frames.len() - 1);
+    let t = view.at / view.step - index as f32;
+    let (frame, next) = (&view.frames[index], &view.frames[next]);
+
+    for mut transform in ball.iter_mut() {
+        transform.translation = frame.ball.lerp(next.ball, t);
+    }
+    for (planet, mut transform) in planets.iter_mut() {
+        if let (Some(pos), Some(next)) = (frame.planets.get(planet.index), next.planets.get(planet.index)) {
+            transform.translation = pos.lerp(*next, t);
+        }
+    }
+    for (ground, mut transform) in riders.iter_mut() {
+        if let Ok((_, planet)) = planets.get(ground.planet) {
+            transform.translation = planet.translation + ground.offset;
+        }
+    }
+
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = format!(
+            "Replay {} / {}   Space to {}, left and right to scrub, V to go back",
+            format_time(view.at),
+            format_time(view.duration()),
+            if view.playing { "pause" } else { "play" }
+        );
+    }
+}
+
+fn restore_positions(
+    mut commands: Commands,
+    view: Option<Res<ReplayView>>,
+    mut transforms: Query<&mut Transform>,
+) {
+    if let Some(view) = view {
+        for (entity, pos) in &view.live {
+            if let Ok(mut transform) = transforms.get_mut(*entity) {
+                transform.translation = *pos;
+            }
+        }
+    }
+    commands.remove_resource::<ReplayView>();
+}
+
+/// Where shared shots are read from: `shot.ron` in the data directory
+/// natively, next to the save.
+#[cfg(not(target_arch = "wasm32"))]
+mod shared {
+    use directories::ProjectDirs;
+
+    pub const PLACE: &str = "shot.ron";
+
+    pub fn read() -> Option<String> {
+        let dirs = ProjectDirs::from("", "", "jamhacks")?;
+        std::fs::read_to_string(dirs.data_dir().join(PLACE)).ok()
+    }
+}
+
+/// In the browser, a shot is pasted into local storage from the console.
+#[cfg(target_arch = "wasm32")]
+mod shared {
+    pub const PLACE: &str = "jamhacks-shot";
+
+    pub fn read() -> Option<String> {
+        web_sys::window()?.local_storage().ok()??.get_item(PLACE).ok()?
+    }
+}
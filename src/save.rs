@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::progress::Progress;
+
+/// Bumped whenever `SaveFile` changes in a way old files can't be read as.
+/// `parse` has to keep reading every older version.
+const SAVE_VERSION: u32 = 1;
+
+/// Loads `Progress` when the game starts and writes it back whenever it
+/// changes: to a file in the user's data directory natively, or to local
+/// storage in the browser.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(load())
+            .add_system(save_progress);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    progress: Progress,
+}
+
+/// Just enough of any version of `SaveFile` to know how to read the rest.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+fn parse(text: &str) -> Result<Progress, anyhow::Error> {
+    let header = ron::de::from_str::<SaveHeader>(text)?;
+    match header.version {
+        1 => Ok(ron::de::from_str::<SaveFile>(text)?.progress),
+        version => Err(anyhow::anyhow!(
+            "save version {} is newer than this game understands ({})",
+            version,
+            SAVE_VERSION
+        )),
+    }
+}
+
+fn load() -> Progress {
+    let text = match storage::read() {
+        Some(text) => text,
+        None => return Progress::default(),
+    };
+
+    match parse(&text) {
+        Ok(progress) => progress,
+        Err(err) => {
+            // Keep the old file around rather than overwriting it on the
+            // next save, in case it can be fixed by hand.
+            warn!("Couldn't read save data, starting afresh: {}", err);
+            storage::back_up(&text);
+            Progress::default()
+        }
+    }
+}
+
+fn save_progress(progress: Res<Progress>) {
+    if !progress.is_changed() || progress.is_added() {
+        return;
+    }
+
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        progress: progress.clone(),
+    };
+    let result = ron::ser::to_string_pretty(&file, default())
+        .map_err(anyhow::Error::from)
+        .and_then(|text| storage::write(&text));
+    if let Err(err) = result {
+        error!("Couldn't save progress: {}", err);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::fs;
+    use std::path::PathBuf;
+    use directories::ProjectDirs;
+
+    fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "jamhacks").map(|dirs| dirs.data_dir().join("save.ron"))
+    }
+
+    pub fn read() -> Option<String> {
+        fs::read_to_string(path()?).ok()
+    }
+
+    /// Writes next to the save and renames over it, so a crash halfway
+    /// through can't leave a truncated file behind.
+    pub fn write(text: &str) -> Result<(), anyhow::Error> {
+        let path = path().ok_or_else(|| anyhow::anyhow!("no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("ron.tmp");
+        fs::write(&temp, text)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    pub fn back_up(text: &str) {
+        if let Some(path) = path() {
+            let _ = fs::write(path.with_extension("ron.bak"), text);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    const KEY: &str = "jamhacks-save";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn read() -> Option<String> {
+        local_storage()?.get_item(KEY).ok()?
+    }
+
+    pub fn write(text: &str) -> Result<(), anyhow::Error> {
+        local_storage()
+            .ok_or_else(|| anyhow::anyhow!("no local storage"))?
+            .set_item(KEY, text)
+            .map_err(|err| anyhow::anyhow!("{:?}", err))
+    }
+
+    pub fn back_up(text: &str) {
+        if let Some(storage) = local_storage() {
+            let _ = storage.set_item(&format!("{}.bak", KEY), text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress() -> Progress {
+        let mut progress = Progress::default();
+        progress.record("one", 3, 12.5);
+        progress.unlock("two");
+        progress
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let file = SaveFile {
+            version: SAVE_VERSION,
+            progress: progress(),
+        };
+        let text = ron::ser::to_string_pretty(&file, default()).unwrap();
+        assert_eq!(parse(&text).unwrap(), progress());
+    }
+
+    #[test]
+    fn reads_version_one() {
+        let text = r#"(
+            version: 1,
+            progress: (
+                unlocked: ["two"],
+                best: {"one": (strokes: 3, time: 12.5)},
+            ),
+        )"#;
+        assert_eq!(parse(text).unwrap(), progress());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let text = "(version: 99, progress: ())";
+        let err = parse(text).unwrap_err();
+        assert!(err.to_string().contains("99"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let text = ron::ser::to_string(&SaveFile {
+            version: SAVE_VERSION,
+            progress: progress(),
+        }).unwrap();
+        for broken in ["", "not a save", "(progress: ())", &text[..text.len() / 2]] {
+            assert!(parse(broken).is_err(), "{:?} parsed", broken);
+        }
+    }
+}
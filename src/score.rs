@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use crate::levels::CurrentLevel;
+use crate::physics::TimeWarp;
+use crate::setup::*;
+
+/// Keeps score over a run: strokes and time on the current level, and how
+/// every finished level went.
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Scorecard>()
+            .add_system_set(SystemSet::on_enter(LevelState::Playing).with_system(reset_level_score))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(add_score_hud))
+            .add_system_set(SystemSet::on_update(GameState::Game)
+                .with_system(update_score_hud)
+                .with_system(tick_level_time)
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(add_summary))
+            .add_system_set(SystemSet::on_exit(GameState::Splash).with_system(crate::despawn::<Summary>));
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LevelScore {
+    pub level: usize,
+    pub strokes: u32,
+    pub par: u32,
+    /// Seconds spent on the level, not counting menus.
+    pub time: f32,
+}
+
+#[derive(Default)]
+pub struct Scorecard {
+    /// Shots taken on the level being played.
+    pub strokes: u32,
+    /// Seconds spent on the level being played.
+    pub time: f32,
+    pub levels: Vec<LevelScore>,
+}
+
+impl Scorecard {
+    pub fn finish(&mut self, level: usize, par: u32) {
+        self.levels.push(LevelScore {
+            level,
+            strokes: self.strokes,
+            par,
+            time: self.time,
+        });
+        self.strokes = 0;
+        self.time = 0.0;
+    }
+
+    /// Forgets the last finished level, so it can be played again.
+    pub fn retry(&mut self) {
+        self.levels.pop();
+    }
+
+    pub fn last(&self) -> Option<&LevelScore> {
+        self.levels.last()
+    }
+
+    pub fn total_strokes(&self) -> u32 {
+        self.levels.iter().map(|score| score.strokes).sum()
+    }
+
+    pub fn total_par(&self) -> u32 {
+        self.levels.iter().map(|score| score.par).sum()
+    }
+
+    pub fn total_time(&self) -> f32 {
+        self.levels.iter().map(|score| score.time).sum()
+    }
+
+    /// A line per finished level and one for the total.
+    pub fn summary(&self) -> String {
+        let mut lines = self.levels
+            .iter()
+            .map(|score| format!(
+                "Level {}: {} strokes, par {} ({}) in {}",
+                score.level + 1,
+                score.strokes,
+                score.par,
+                relative_to_par(score.strokes, score.par),
+                format_time(score.time)
+            ))
+            .collect::<Vec<_>>();
+        lines.push(format!(
+            "Total: {} strokes, par {} ({}) in {}",
+            self.total_strokes(),
+            self.total_par(),
+            relative_to_par(self.total_strokes(), self.total_par()),
+            format_time(self.total_time())
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Golf style score against par, e.g. "+2", "-1" or "E".
+pub fn relative_to_par(strokes: u32, par: u32) -> String {
+    match strokes as i64 - par as i64 {
+        0 => "E".to_string(),
+        diff if diff > 0 => format!("+{}", diff),
+        diff => diff.to_string(),
+    }
+}
+
+#[derive(Component)]
+struct ScoreHud;
+
+#[derive(Component)]
+struct Summary;
+
+fn reset_level_score(mut scorecard: ResMut<Scorecard>) {
+    scorecard.strokes = 0;
+    scorecard.time = 0.0;
+}
+
+fn tick_level_time(time: Res<Time>, mut scorecard: ResMut<Scorecard>) {
+    scorecard.time += time.delta_seconds();
+}
+
+/// Minutes and seconds, e.g. "1:05.3".
+pub fn format_time(seconds: f32) -> String {
+    format!("{}:{:04.1}", (seconds / 60.0) as u32, seconds % 60.0)
+}
+
+pub(crate) fn text_style(assets: &AssetServer, size: f32) -> TextStyle {
+    TextStyle {
+        font: assets.load("Ubuntu-Bold.ttf"),
+        font_size: size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    }
+}
+
+fn add_score_hud(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(10.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section("", text_style(&assets, 30.0), default()),
+        ..default()
+    })
+        .insert(ScoreHud)
+        .insert(GameElement);
+}
+
+fn update_score_hud(
+    scorecard: Res<Scorecard>,
+    current: Res<CurrentLevel>,
+    levels: Res<crate::levels::Levels>,
+    packs: Res<Assets<crate::levels::LevelPack>>,
+    level_assets: Res<Assets<crate::levels::Level>>,
+    warp: Res<TimeWarp>,
+    mut hud: Query<&mut Text, With<ScoreHud>>,
+) {
+    let level = levels.current(&current, &packs, &level_assets);
+    let par = level.map_or(0, |level| level.par);
+    let title = match (&levels.custom, level) {
+        (Some(_), Some(level)) => level.name.clone(),
+        _ => format!("Level {}", current.0 + 1),
+    };
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = format!(
+            "{}   Strokes {}   Par {}   {}   Time x{}",
+            title,
+            scorecard.strokes,
+            par,
+            format_time(scorecard.time),
+            warp.factor()
+        );
+    }
+}
+
+fn add_summary(mut commands: Commands, scorecard: Res<Scorecard>, assets: Res<AssetServer>) {
+    if scorecard.levels.is_empty() {
+        return;
+    }
+
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section(scorecard.summary(), text_style(&assets, 28.0), default()),
+        ..default()
+    })
+        .insert(Summary);
+}
@@ -1,11 +1,10 @@
 use crate::{Handle, Mesh, StandardMaterial, Vec2, Vec3};
 use bevy::prelude::*;
 use dolly::prelude::*;
+use jamhacks_physics::{self as physics, Ball, Motion};
 
 pub const RESOLUTION: f32 = 16.0 / 9.0;
 
-pub const GRAVITY: f32 = 6.67e-2;
-
 pub const BALL_RAD: f32 = 0.1;
 
 #[derive(Component)]
@@ -16,15 +15,44 @@ pub struct GravityAffected {
 
 #[derive(Component)]
 pub struct Planet {
+    /// Position in the level's planet list, which orbits refer to.
+    pub index: usize,
     pub mass: f32,
     pub radius: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub vel: Vec3,
+    pub motion: Motion,
+}
+
+impl Planet {
+    pub fn to_physics(&self, pos: Vec3) -> physics::Planet {
+        physics::Planet {
+            pos,
+            vel: self.vel,
+            mass: self.mass,
+            radius: self.radius,
+            restitution: self.restitution,
+            friction: self.friction,
+            motion: self.motion.clone(),
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct Dynamics {
     pub acc: Vec3,
     pub vel: Vec3,
-    pub recent_collisions: f32,
+}
+
+impl Dynamics {
+    pub fn to_ball(&self, pos: Vec3, radius: f32) -> Ball {
+        Ball {
+            pos,
+            vel: self.vel,
+            radius,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -51,29 +79,54 @@ pub struct MainBall;
 pub struct ProjectedResources {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
+    pub bounce_material: Handle<StandardMaterial>,
+    pub rest_material: Handle<StandardMaterial>,
 }
 
 #[derive(Component)]
 pub struct UIMainCamera;
 
-pub struct DeltaTime {
-    pub time: f32,
-}
-
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum GameState {
     Splash,
     Game,
+    /// Pushed on top of `Game` when the ball reaches the target.
+    LevelComplete,
+    /// Pushed on top of `Game` when the last level is finished.
+    GameComplete,
+    /// Pushed on top of `Game` by pressing escape.
+    Paused,
+    /// Pushed on top of `Game` to watch the last shot again.
+    Replay,
+    /// Building a level, in place of `Game`.
+    Editor,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LevelState {
+    None,
+    Playing,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CameraState {
     Follow,
     Around {
-        pos: Vec3,
+        planet: Entity,
     },
 }
 
+/// Something sitting on a planet's surface, which moves along with it.
+#[derive(Component, Copy, Clone)]
+pub struct OnGround {
+    pub planet: Entity,
+    pub offset: Vec3,
+}
+
+/// Where the ball was launched from, to put it back if it's lost.
+#[derive(Component)]
+pub struct LastRest(pub OnGround);
+
 #[derive(Component)]
 pub struct MainMenuElement;
 
@@ -0,0 +1,221 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// How far away the stars are, well past anything in a level.
+const SKY_RADIUS: f32 = 5000.0;
+
+/// Each star is a tiny octahedron, which from this far away is a dot.
+const CORNERS: [[f32; 3]; 6] = [
+    [1.0, 0.0, 0.0],
+    [-1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, -1.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [0.0, 0.0, -1.0],
+];
+const FACES: [[u32; 3]; 8] = [
+    [0, 2, 4], [4, 2, 1], [1, 2, 5], [5, 2, 0],
+    [4, 3, 0], [1, 3, 4], [5, 3, 1], [0, 3, 5],
+];
+
+/// The sky behind everything, built once from `Starfield` and rebuilt only if
+/// it changes. All the stars are one mesh. Each star's colour is one texel of
+/// a small texture, so twinkling only means rewriting the texture.
+pub struct StarfieldPlugin;
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Starfield>()
+            .add_system(build_starfield)
+            .add_system(twinkle);
+    }
+}
+
+/// Insert before adding the plugin to change the sky, or change it later to
+/// rebuild it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Starfield {
+    /// The same seed always gives the same sky.
+    pub seed: u64,
+    /// Stars over the whole sky.
+    pub density: usize,
+    /// Typical colour temperature of a star in kelvin, lower is redder and
+    /// higher bluer. Each star is up to `temperature_spread` either side.
+    pub temperature: f32,
+    pub temperature_spread: f32,
+    /// How much the stars flicker, from 0 for not at all to 1 for fading
+    /// right out.
+    pub twinkle: f32,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            density: 4000,
+            temperature: 6000.0,
+            temperature_spread: 3000.0,
+            twinkle: 0.4,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Stars {
+    texture: Handle<Image>,
+    stars: Vec<Star>,
+}
+
+struct Star {
+    colour: [u8; 3],
+    /// Twinkles per second.
+    rate: f32,
+    phase: f32,
+}
+
+fn build_starfield(
+    mut commands: Commands,
+    starfield: Res<Starfield>,
+    old: Query<Entity, With<Stars>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !starfield.is_changed() {
+        return;
+    }
+    for entity in old.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(starfield.seed);
+    let side = (starfield.density as f32).sqrt().ceil().max(1.0) as u32;
+
+    let mut positions = Vec::with_capacity(starfield.density * CORNERS.len());
+    let mut normals = Vec::with_capacity(positions.capacity());
+    let mut uvs = Vec::with_capacity(positions.capacity());
+    let mut indices = Vec::with_capacity(starfield.density * FACES.len() * 3);
+    let mut stars = Vec::with_capacity(starfield.density);
+
+    for i in 0..starfield.density {
+        let centre = sky_direction(&mut rng) * SKY_RADIUS;
+        let size = rng.gen_range(4.0..12.0);
+        let uv = [
+            ((i as u32 % side) as f32 + 0.5) / side as f32,
+            ((i as u32 / side) as f32 + 0.5) / side as f32,
+        ];
+
+        let first = positions.len() as u32;
+        for corner in CORNERS {
+            positions.push((centre + Vec3::from(corner) * size).to_array());
+            normals.push(corner);
+            uvs.push(uv);
+        }
+        indices.extend(FACES.iter().flatten().map(|corner| first + corner));
+
+        let spread = starfield.temperature_spread;
+        let kelvin = starfield.temperature + rng.gen_range(-1.0..=1.0) * spread;
+        stars.push(Star {
+            colour: blackbody(kelvin),
+            rate: rng.gen_range(0.3..1.5),
+            phase: rng.gen_range(0.0..TAU),
+        });
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+
+    let mut image = Image::new(
+        Extent3d {
+            width: side,
+            height: side,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        vec![0; (side * side * 4) as usize],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    paint(&mut image, &stars, 0.0, 0.0);
+    let texture = images.add(image);
+
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(mesh),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(texture.clone()),
+            unlit: true,
+            ..default()
+        }),
+        ..default()
+    })
+        .insert(Stars { texture, stars });
+}
+
+fn twinkle(
+    time: Res<Time>,
+    starfield: Res<Starfield>,
+    stars: Query<&Stars>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if starfield.twinkle <= 0.0 {
+        return;
+    }
+    for stars in stars.iter() {
+        if let Some(image) = images.get_mut(&stars.texture) {
+            paint(image, &stars.stars, time.seconds_since_startup() as f32, starfield.twinkle);
+        }
+    }
+}
+
+/// Writes each star's colour into its texel, dimmed by how far through a
+/// twinkle it is at `time`.
+fn paint(image: &mut Image, stars: &[Star], time: f32, twinkle: f32) {
+    for (texel, star) in image.data.chunks_exact_mut(4).zip(stars) {
+        // Mostly bright with the odd quick dip, rather than a steady pulse.
+        let dip = (0.5 + 0.5 * (time * star.rate * TAU + star.phase).sin()).powi(4);
+        let brightness = 1.0 - twinkle * dip;
+        for (channel, colour) in texel.iter_mut().zip(star.colour) {
+            *channel = (colour as f32 * brightness) as u8;
+        }
+        texel[3] = 255;
+    }
+}
+
+/// A random direction, evenly spread over the sky.
+fn sky_direction(rng: &mut impl Rng) -> Vec3 {
+    let y = rng.gen_range(-1.0..=1.0f32);
+    let angle = rng.gen_range(0.0..TAU);
+    let ring = (1.0 - y * y).sqrt();
+    Vec3::new(ring * angle.cos(), y, ring * angle.sin())
+}
+
+/// The colour of something glowing at `kelvin`, following Tanner Helland's
+/// fit to the blackbody curve.
+fn blackbody(kelvin: f32) -> [u8; 3] {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.69873 * (t - 60.0).powf(-0.13320476)
+    };
+    let green = if t <= 66.0 {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12216 * (t - 60.0).powf(-0.075514846)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.51773 * (t - 10.0).ln() - 305.0448
+    };
+    [red, green, blue].map(|channel| channel.clamp(0.0, 255.0) as u8)
+}
@@ -1,87 +1,293 @@
 use bevy::prelude::*;
 use bevy::ui::FocusPolicy;
-use crate::{GameState, LevelNumber};
+use crate::{GameState, LevelState, MainMenuElement};
+use crate::daily::{daily_label, DailyButton};
+use crate::editor::EditorButton;
+use crate::level_select::LevelGrid;
+use crate::levels::{go_to_level, CurrentLevel, LevelPack, Levels};
+use crate::progress::Progress;
+use crate::random_level::{RandomButton, RandomLevel};
+use crate::score::{text_style, Scorecard};
 
 pub struct MainMenuPlugin;
 
 #[derive(Component)]
 pub struct ButtonActive(bool);
 
-struct UiAssets {
-    font: Handle<Font>,
-    button: Handle<Image>,
-    button_pressed: Handle<Image>
+#[derive(Component)]
+struct StartButton;
+
+pub(crate) struct UiAssets {
+    pub(crate) font: Handle<Font>,
+    pub(crate) button: Handle<Image>,
+    pub(crate) button_pressed: Handle<Image>
 }
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_menu)
+        app.add_startup_system(load_ui_assets)
+            .add_system_set(SystemSet::on_enter(GameState::Splash).with_system(setup_menu))
             .add_system_set(SystemSet::on_exit(GameState::Splash).with_system(despawn_menu))
+            .add_system(press_buttons)
             .add_system(handle_start_button);
     }
 }
 
-fn despawn_menu(mut commands: Commands, button_query: Query<Entity, With<Button>>) {
-    for ent in button_query.iter() {
+fn despawn_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenuElement>>) {
+    for ent in menu_query.iter() {
         commands.entity(ent).despawn_recursive();
     }
 }
 
+/// Starts a new run from level `index`.
+pub(crate) fn start_game(
+    index: usize,
+    game_state: &mut State<GameState>,
+    level_state: &mut State<LevelState>,
+    current: &mut CurrentLevel,
+    scorecard: &mut Scorecard,
+) {
+    game_state.set(GameState::Game).unwrap();
+    *scorecard = Scorecard::default();
+    go_to_level(index, current, level_state);
+}
+
+/// Shows the pressed image on any menu button while it's held down.
+fn press_buttons(
+    interaction_query: Query<(&Children, &Interaction), Changed<Interaction>>,
+    mut image_query: Query<&mut UiImage>,
+    ui_assets: Res<UiAssets>,
+) {
+    for (children, interaction) in interaction_query.iter() {
+        let child = children.iter().next().unwrap();
+        if let Ok(mut image) = image_query.get_mut(*child) {
+            image.0 = match interaction {
+                Interaction::Clicked => ui_assets.button_pressed.clone(),
+                Interaction::Hovered | Interaction::None => ui_assets.button.clone(),
+            };
+        }
+    }
+}
+
+/// A button with `label` on it, tagged with `action` so the menu it's in
+/// can tell which one was clicked.
+pub(crate) fn spawn_button(
+    parent: &mut ChildBuilder,
+    ui_assets: &UiAssets,
+    label: &str,
+    action: impl Component,
+) {
+    parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            size: Size::new(Val::Px(260.0), Val::Px(90.0)),
+            margin: Rect::all(Val::Px(10.0)),
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    })
+        .insert(action)
+        .with_children(|parent: &mut ChildBuilder| {
+        parent.spawn_bundle(ImageBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            image: ui_assets.button.clone().into(),
+            ..Default::default()
+        })
+            .insert(FocusPolicy::Pass).with_children(|parent: &mut ChildBuilder| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 30.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                    Default::default()),
+                focus_policy: FocusPolicy::Pass,
+                ..Default::default()
+            });
+        });
+    });
+}
+
+/// Changes the label of a button made with `spawn_button`, which is on the
+/// button's image.
+pub(crate) fn set_button_label(
+    children: &Children,
+    images: &Query<&Children, With<UiImage>>,
+    texts: &mut Query<&mut Text>,
+    label: &str,
+) {
+    for image in children.iter() {
+        for text in images.get(*image).map_or(&[][..], |children| &children[..]) {
+            if let Ok(mut text) = texts.get_mut(*text) {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    }
+}
+
+/// A dimmed full screen panel with `title`, `body` and a row of `buttons`,
+/// tagged with `marker` so it can be despawned.
+pub(crate) fn spawn_panel<T: Component + Copy>(
+    commands: &mut Commands,
+    assets: &AssetServer,
+    ui_assets: &UiAssets,
+    marker: impl Component,
+    title: &str,
+    body: String,
+    buttons: &[(&str, T)],
+) {
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        ..default()
+    })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(title, text_style(assets, 60.0), default()),
+                ..default()
+            });
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(20.0)),
+                    ..default()
+                },
+                text: Text::with_section(body, text_style(assets, 30.0), default()),
+                ..default()
+            });
+            parent.spawn_bundle(NodeBundle {
+                color: Color::NONE.into(),
+                ..default()
+            })
+                .with_children(|parent| {
+                    for (label, action) in buttons {
+                        spawn_button(parent, ui_assets, label, *action);
+                    }
+                });
+        });
+}
+
 fn handle_start_button(
-    mut commands: Commands,
     mut interaction_query: Query<
-        (&Children, &mut ButtonActive, &Interaction),
-        Changed<Interaction>,
+        (&mut ButtonActive, &Interaction),
+        (Changed<Interaction>, With<StartButton>),
     >,
-    mut image_query: Query<&mut UiImage>,
-    ui_assets: Res<UiAssets>,
     mut game_state: ResMut<State<GameState>>,
-    mut level_state: ResMut<State<LevelNumber>>,
+    mut level_state: ResMut<State<LevelState>>,
+    mut current: ResMut<CurrentLevel>,
+    levels: Res<Levels>,
+    packs: Res<Assets<LevelPack>>,
+    asset_server: Res<AssetServer>,
+    mut scorecard: ResMut<Scorecard>,
     //ascii: Res<AsciiSheet>,
 ) {
-    for (children, mut active, interaction) in interaction_query.iter_mut() {
-        let child = children.iter().next().unwrap();
-        let mut image = image_query.get_mut(*child).unwrap();
-
+    for (mut active, interaction) in interaction_query.iter_mut() {
         match interaction {
             Interaction::Clicked => {
-                if active.0 {
-                    image.0 = ui_assets.button_pressed.clone();
-                    game_state.set(GameState::Game).unwrap();
-                    level_state.set(LevelNumber::One).unwrap();
+                if active.0 && levels.is_loaded(&asset_server, &packs) {
+                    start_game(0, &mut game_state, &mut level_state, &mut current, &mut scorecard);
                     //Here change gamestate
                     //create_fadeout(&mut commands, Some(GameState::Splash), &ascii);
                     active.0 = false;
                 }
             }
-            Interaction::Hovered | Interaction::None => {
-                image.0 = ui_assets.button.clone();
-            }
+            Interaction::Hovered | Interaction::None => {}
         }
     }
 }
 
-fn setup_menu(mut commands: Commands, assets: Res<AssetServer>) {
-    let ui_assets = UiAssets {
+fn load_ui_assets(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(UiAssets {
         font: assets.load("Ubuntu-Bold.ttf"),
         button: assets.load("start_1.png"),
         button_pressed: assets.load("start_clicked_1.png")
-    };
+    });
 
     commands.spawn_bundle(UiCameraBundle::default());
-    commands.spawn_bundle(ButtonBundle {
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    random: Res<RandomLevel>,
+    progress: Res<Progress>,
+) {
+    commands.spawn_bundle(NodeBundle {
         style: Style {
-            align_self: AlignSelf::Center,
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
-            size: Size::new(Val::Percent(50.0), Val::Percent(40.0)),
-            margin: Rect::all(Val::Auto),
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    })
+        .insert(MainMenuElement)
+        .with_children(|parent: &mut ChildBuilder| {
+        spawn_start_button(parent, &ui_assets);
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(80.0), Val::Auto),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+            .with_children(|parent: &mut ChildBuilder| {
+            spawn_button(parent, &ui_assets, &daily_label(&progress), DailyButton);
+            spawn_button(parent, &ui_assets, "Level editor", EditorButton);
+            spawn_button(parent, &ui_assets, "Random level", RandomButton::Play);
+            spawn_button(parent, &ui_assets, random.difficulty.label(), RandomButton::Difficulty);
+        });
+
+        // Filled in by `level_select` once the levels have loaded.
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(80.0), Val::Auto),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+            .insert(LevelGrid);
+    });
+}
+
+fn spawn_start_button(parent: &mut ChildBuilder, ui_assets: &UiAssets) {
+    parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            size: Size::new(Val::Percent(40.0), Val::Percent(25.0)),
+            margin: Rect::all(Val::Px(20.0)),
             ..Default::default()
         },
         color: Color::NONE.into(),
         ..Default::default()
     })
         .insert(ButtonActive(true))
+        .insert(StartButton)
         .with_children(|parent: &mut ChildBuilder| {
         parent.spawn_bundle(ImageBundle {
             style: Style {
@@ -108,7 +314,5 @@ fn setup_menu(mut commands: Commands, assets: Res<AssetServer>) {
             });
         });
     });
-    commands.insert_resource(ui_assets);
-
 }
 